@@ -0,0 +1,169 @@
+//! Compares the queue storage with a `VecDeque`, on single operations and on
+//!  what queue-heavy samples do to their queues. Needs a nightly compiler:
+//!  `cargo bench --bench queue`.
+
+
+#![feature(test)]
+
+extern crate test;
+
+use sisyphus::expr::Expr;
+use sisyphus::exec::{ Executor, Limits, Observer, Rope };
+use sisyphus::parser;
+use std::collections::VecDeque;
+use std::{ fs, io };
+use std::sync::{ Arc, Mutex };
+use test::{ Bencher, black_box };
+
+
+const LEN : usize = 100_000;
+
+
+fn exprs() -> impl Iterator<Item = Expr> {
+    parser::parse("print + 1 get queue 2").unwrap().into_iter().cycle().take(LEN)
+}
+
+
+/// Something a sample did to the main queue, recorded so that it can be done
+///  again to either storage.
+enum Op {
+    Push   (Expr),
+    Insert (usize, Expr),
+    Set    (usize, Expr),
+    Pop
+}
+
+struct Record(Arc<Mutex<Vec<Op>>>);
+
+// Observers count the expression executed last as index 0.
+impl Observer for Record {
+    fn before_tick(&mut self, _tick : u64, _expr : &Expr) {
+        self.0.lock().unwrap().push(Op::Pop);
+    }
+    fn on_push(&mut self, expr : &Expr) {
+        self.0.lock().unwrap().push(Op::Push(expr.clone()));
+    }
+    fn on_set(&mut self, index : usize, expr : &Expr) {
+        self.0.lock().unwrap().push(Op::Set(index - 1, expr.clone()));
+    }
+    fn on_insert(&mut self, index : usize, expr : &Expr) {
+        self.0.lock().unwrap().push(Op::Insert(index - 1, expr.clone()));
+    }
+}
+
+trait Queue : Default {
+    fn push(&mut self, expr : Expr);
+    fn insert(&mut self, index : usize, expr : Expr);
+    fn set(&mut self, index : usize, expr : Expr);
+    fn pop(&mut self);
+}
+
+impl Queue for VecDeque<Expr> {
+    fn push(&mut self, expr : Expr) { self.push_back(expr); }
+    fn insert(&mut self, index : usize, expr : Expr) { VecDeque::insert(self, index, expr); }
+    fn set(&mut self, index : usize, expr : Expr) { self[index] = expr; }
+    fn pop(&mut self) { self.pop_front(); }
+}
+
+impl Queue for Rope<Expr> {
+    fn push(&mut self, expr : Expr) { self.push_back(expr); }
+    fn insert(&mut self, index : usize, expr : Expr) { Rope::insert(self, index, expr); }
+    fn set(&mut self, index : usize, expr : Expr) { self[index] = expr; }
+    fn pop(&mut self) { self.pop_front(); }
+}
+
+/// Runs a sample for `ticks` ticks, then times doing what it did to the main
+///  queue again, to `Q`.
+fn sample<Q : Queue>(b : &mut Bencher, name : &str, ticks : u64) {
+    let source = fs::read_to_string(format!("samples/{}", name)).unwrap();
+    let ops    = Arc::new(Mutex::new(Vec::new()));
+    let mut e  = Executor::new().with_output(io::sink()).with_observer(Record(ops.clone()));
+    e.push_exprs(parser::parse(&source).unwrap());
+    e.run(Limits::new(ticks));
+    let ops = ops.lock().unwrap();
+    b.iter(|| {
+        let mut queue = Q::default();
+        for op in ops.iter() {
+            match (op) {
+                Op::Push   (expr)        => queue.push(expr.clone()),
+                Op::Insert (index, expr) => queue.insert(*index, expr.clone()),
+                Op::Set    (index, expr) => queue.set(*index, expr.clone()),
+                Op::Pop                  => queue.pop()
+            }
+        }
+        black_box(queue)
+    });
+}
+
+#[bench]
+fn sample_big_list_vec_deque(b : &mut Bencher) {
+    sample::<VecDeque<_>>(b, "big_list.push", 200);
+}
+
+#[bench]
+fn sample_big_list_rope(b : &mut Bencher) {
+    sample::<Rope<_>>(b, "big_list.push", 200);
+}
+
+#[bench]
+fn sample_prime_main_vec_deque(b : &mut Bencher) {
+    sample::<VecDeque<_>>(b, "prime_main.push", 20_000);
+}
+
+#[bench]
+fn sample_prime_main_rope(b : &mut Bencher) {
+    sample::<Rope<_>>(b, "prime_main.push", 20_000);
+}
+
+
+#[bench]
+fn middle_insert_vec_deque(b : &mut Bencher) {
+    let mut queue = exprs().collect::<VecDeque<_>>();
+    let expr      = queue[0].clone();
+    b.iter(|| queue.insert(LEN / 2, expr.clone()));
+}
+
+#[bench]
+fn middle_insert_rope(b : &mut Bencher) {
+    let mut queue = exprs().collect::<Rope<_>>();
+    let expr      = queue[0].clone();
+    b.iter(|| queue.insert(LEN / 2, expr.clone()));
+}
+
+#[bench]
+fn get_vec_deque(b : &mut Bencher) {
+    let queue = exprs().collect::<VecDeque<_>>();
+    b.iter(|| for i in (0..LEN).step_by(97) { black_box(&queue[black_box(i)]); });
+}
+
+#[bench]
+fn get_rope(b : &mut Bencher) {
+    let queue = exprs().collect::<Rope<_>>();
+    b.iter(|| for i in (0..LEN).step_by(97) { black_box(&queue[black_box(i)]); });
+}
+
+#[bench]
+fn set_vec_deque(b : &mut Bencher) {
+    let mut queue = exprs().collect::<VecDeque<_>>();
+    let expr      = queue[0].clone();
+    b.iter(|| for i in (0..LEN).step_by(97) { queue[black_box(i)] = expr.clone(); });
+}
+
+#[bench]
+fn set_rope(b : &mut Bencher) {
+    let mut queue = exprs().collect::<Rope<_>>();
+    let expr      = queue[0].clone();
+    b.iter(|| for i in (0..LEN).step_by(97) { queue[black_box(i)] = expr.clone(); });
+}
+
+#[bench]
+fn clone_vec_deque(b : &mut Bencher) {
+    let queue = exprs().collect::<VecDeque<_>>();
+    b.iter(|| black_box(queue.clone()));
+}
+
+#[bench]
+fn clone_rope(b : &mut Bencher) {
+    let queue = exprs().collect::<Rope<_>>();
+    b.iter(|| black_box(queue.clone()));
+}
@@ -14,31 +14,143 @@ pub fn queue() -> &'static mut Vec<String> { docs_only() }
 pub fn r#if<T>(condition : bool, when_true : T, when_false : T) -> T { docs_only() }
 
 
+/// Adds two values.
+///
+/// Strings are concatenated with anything, and arrays with arrays.
+#[doc(alias = "+")]
+pub fn add<T>(left : T, right : T) -> T { docs_only() }
+
+/// Subtracts the right value from the left one.
+#[doc(alias = "-")]
+pub fn sub<T>(left : T, right : T) -> T { docs_only() }
+
+/// Multiplies two values.
+///
+/// Strings and arrays are repeated when multiplied by an int.
+#[doc(alias = "*")]
+pub fn mul<T>(left : T, right : T) -> T { docs_only() }
+
+/// Divides the left value by the right one.
+#[doc(alias = "/")]
+pub fn div<T>(left : T, right : T) -> T { docs_only() }
+
+/// Returns the remainder of dividing the left value by the right one.
+#[doc(alias = "%")]
+pub fn rem<T>(left : T, right : T) -> T { docs_only() }
+
+/// Negates a bool or number, or reverses a string or array.
+#[doc(alias = "!")]
+pub fn not<T>(value : T) -> T { docs_only() }
+
+
+/// Returns whether two values are equal.
+#[doc(alias = "=")]
+pub fn equals<T>(left : T, right : T) -> bool { docs_only() }
+
+/// Returns whether the left value is greater than the right one.
+#[doc(alias = ">")]
+pub fn greater<T>(left : T, right : T) -> bool { docs_only() }
+
+/// Returns whether the left value is greater than or equal to the right one.
+#[doc(alias = ">=")]
+pub fn greater_equals<T>(left : T, right : T) -> bool { docs_only() }
+
+/// Returns whether the left value is less than the right one.
+#[doc(alias = "<")]
+pub fn less<T>(left : T, right : T) -> bool { docs_only() }
+
+/// Returns whether the left value is less than or equal to the right one.
+#[doc(alias = "<=")]
+pub fn less_equals<T>(left : T, right : T) -> bool { docs_only() }
+
+
 /// Gets a single entry from the given array.
 pub fn get<T>(array : Array<T>, line : usize) -> String { docs_only() }
 
+/// Gets the entries from start up to, but not including, end.
+pub fn gets<T>(array : Array<T>, start : usize, end : usize) -> Array<String> { docs_only() }
+
 
 /// Pushes a single entry at the end of the given array.
 ///
-/// Returns the index at which it was placed.
-pub fn push<T>(array : &mut Array<T>, instruction : String) -> UInt { docs_only() }
+/// Returns the array with the entry added.
+pub fn push<T>(array : &mut Array<T>, instruction : String) -> Array<T> { docs_only() }
+
+/// Pushes every entry of an array at the end of the given array.
+///
+/// Returns the array with the entries added.
+pub fn pushes<T>(array : &mut Array<T>, instructions : Array<String>) -> Array<T> { docs_only() }
 
 
 /// Inserts a single entry at some position in the given array.
 pub fn insert<T>(array : &mut Array<T>, line : usize, instruction : String) -> () { docs_only() }
 
+/// Inserts every entry of an array, starting at some position in the given
+///  array.
+pub fn inserts<T>(array : &mut Array<T>, line : usize, instructions : Array<String>) -> () { docs_only() }
+
 
 /// Overwrites a single entry at some position in the given
 ///  array.
 ///
-/// Returns the old instruction.
-pub fn set<T>(array : &mut Array<T>, line : usize, instruction : String) -> String { docs_only() }
+/// Returns the array with the entry overwritten.
+pub fn set<T>(array : &mut Array<T>, line : usize, instruction : String) -> Array<T> { docs_only() }
+
+/// Overwrites consecutive entries, starting at some position in the given
+///  array.
+///
+/// Returns the array with the entries overwritten.
+pub fn sets<T>(array : &mut Array<T>, line : usize, instructions : Array<String>) -> Array<T> { docs_only() }
 
 
 /// Returns the number of elements in the given array.
 pub fn len(array : Array<String>) -> UInt { docs_only() }
 
 
+/// Creates an empty queue with the given name, and returns it.
+///
+/// Named queues hold expressions like the main queue, but are only executed
+///  once scheduled.
+pub fn mkqueue(name : String) -> &'static mut Vec<String> { docs_only() }
+
+/// Returns the queue with the given name.
+pub fn named(name : String) -> &'static mut Vec<String> { docs_only() }
+
+/// Moves count entries from the front of one queue to the end of another.
+///
+/// Returns the queue the entries were moved to.
+pub fn transfer(from : &mut Vec<String>, to : &mut Vec<String>, count : UInt) -> &'static mut Vec<String> { docs_only() }
+
+/// Sets whether a named queue is executed, taking turns with the main queue
+///  and every other scheduled queue.
+///
+/// Returns the queue.
+pub fn schedule(queue : &mut Vec<String>, scheduled : bool) -> &'static mut Vec<String> { docs_only() }
+
+
+/// Returns the channel with the given name, creating it if there is none.
+///
+/// Channels pass values between a script and the scripts it spawns.
+pub fn channel<T>(name : String) -> &'static mut Channel<T> { docs_only() }
+
+/// Adds a value to the end of a channel.
+///
+/// Returns the channel.
+pub fn send<T>(channel : &mut Channel<T>, value : T) -> &'static mut Channel<T> { docs_only() }
+
+/// Takes the value at the front of a channel.
+///
+/// If the channel is empty, the script waits until another script sends
+///  to it, and then carries on from the `recv`. Nothing the instruction did
+///  before it happens again.
+pub fn recv<T>(channel : &mut Channel<T>) -> T { docs_only() }
+
+/// Starts another script, running the given instruction or array of
+///  instructions. Scripts take turns, one instruction each, in the order
+///  they were started.
+pub fn spawn(instructions : Array<String>) -> () { docs_only() }
+
+
 /// Reads the entirety of a file as a string.
 pub fn fsread(fname : String) -> String { docs_only() }
 
@@ -48,15 +160,56 @@ pub fn fswrite(fname : String, content : String) { docs_only() }
 /// Reads the entirety of a file as an array of bytes.
 pub fn fbread(fname : String) -> Array<UInt> { docs_only() }
 
-/// Writes the an array of bytes to a file, overwriting it.
+/// Writes an array of bytes to a file, overwriting it.
 pub fn fbwrite(fname : String, content : Array<UInt>) { docs_only() }
 
 
+/// Reads a line from the input, without its line ending.
+///
+/// Returns an end_of_input error once there is nothing left to read.
+pub fn readln() -> String { docs_only() }
+
+/// Reads everything left in the input.
+pub fn readall() -> String { docs_only() }
+
+
+/// Returns the ints from start up to, but not including, end.
+pub fn range(start : Int, end : Int) -> Array<Int> { docs_only() }
+
+
+/// Converts a value to its printed form.
+pub fn str<T>(value : T) -> String { docs_only() }
+
+/// Parses the printed form of a value as an int.
+pub fn int<T>(value : T) -> Int { docs_only() }
+
+
+/// Returns the kind and message of an error as a two element array.
+///
+/// Returns unit if the value is not an error.
+pub fn reason<T>(value : T) -> Array<String> { docs_only() }
+
+
+/// Stops the script once the current instruction finishes, with the given
+///  exit code, from 0 to 255.
+pub fn exit(code : Int) -> () { docs_only() }
+
+
+/// Calls a function the program running the script registered under the
+///  given name, with every argument after it.
+///
+/// Takes every remaining argument of the expression it is in, so it has to
+///  be put in parentheses when another argument follows.
+pub fn call<T>(name : String, args : Array<T>) -> T { docs_only() }
+
+
 
 fn docs_only() -> ! { unreachable!() }
 
 pub type Array<T> = Vec<T>;
 
+pub type Channel<T> = std::collections::VecDeque<T>;
+
 pub type UInt  = usize;
 pub type Int   = isize;
 pub type Float = f64;
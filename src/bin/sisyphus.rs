@@ -1,20 +1,563 @@
-use sisyphus;
-use sisyphus::expr::{ Expr, Lit };
-use sisyphus::exec::Executor;
+use sisyphus::dap;
+use sisyphus::debug::{ Breakpoint, Debugger, Pause };
+use sisyphus::exec::{ Executor, Limits, Outcome, Reader, Scheduler, SnapshotError };
+use sisyphus::exec::vfs::{ Access, Jail };
+use sisyphus::expr::Expr;
+use sisyphus::parser::{ self, Render };
+use sisyphus::span::Origin;
+use std::{ env, fs };
+use std::collections::BTreeSet;
+use std::io::{ self, Read, Write };
+use std::process::ExitCode;
+use std::time::Duration;
 
 
-fn main() {
-    let source_file = "samples/big_list.push";
+const USAGE : &str = "\
+Usage: sisyphus [run] <script> [--max-ticks <n>] [--max-queue <n>] [--max-time <s>]
+                      [--jail <dir>] [--read-only] [--stdin-queue] [--save <file>] [--record <file>]
+                      [--trace] [--profile] [--stacks <file>] [--detect-cycles] [--stop-on-error]
+       sisyphus [run] --resume <file> [options]
+       sisyphus check <script>
+       sisyphus fmt <script>
+       sisyphus debug <script>
+       sisyphus dap
 
-    let mut executor = Executor::new();
-    executor.push_exprs([
-        Expr::Push(Box::new((
-            Expr::Lit(Lit::ExprQueue),
-            Expr::FSRead(Box::new(
-                Expr::Lit(Lit::String(source_file.to_string()))
-            ))
-        )))
-    ]);
+Commands:
+    run      Parse and execute the script (default)
+    check    Only parse the script, reporting every error
+    fmt      Print the script in its canonical form, one expression per
+             line. Scripts with a shebang, comments or expressions
+             continued on indented lines are refused, since fmt would
+             drop them
+    debug    Step through the script, reading debugger commands from stdin.
+             Type help for the list of commands
+    dap      Serve the Debug Adapter Protocol over stdin and stdout, for
+             editors to launch scripts with
 
-    while (executor.tick()) { }
+Use - as the script to read it from stdin, except with debug.
+
+Options:
+    --max-ticks <n>  Stop after executing n expressions
+    --max-queue <n>  Stop once more than n expressions are queued
+    --max-time <s>   Stop after running for s seconds
+    --jail <dir>     Only let the script use files inside dir
+    --read-only      Don't let the script write files
+    --stdin-queue    Add each line of stdin to the queue while the script
+                     runs, and keep running until stdin ends. readln and
+                     readall then see no input
+    --save <file>    Write a snapshot of the executor to file once it stops.
+                     Scripts that spawn scripts or use channels can't be saved
+    --resume <file>  Continue from a snapshot, instead of running a script
+    --record <file>  Write a trace of every tick and change to the queue to file
+    --trace          Print every expression to stderr before executing it
+    --profile        Print where the time went to stderr once the script stops
+    --stacks <file>  Write where the time went to file, as collapsed stacks
+                     for flamegraph tools
+    --detect-cycles  Stop once the queue repeats itself, since the script
+                     would then never end
+    --stop-on-error  Stop once an expression in the queue evaluates to an
+                     error, instead of carrying on without its result
+    -h, --help       Print this message
+
+Exit codes:
+    0  Success
+    1  A file given in the arguments could not be read or written
+    2  Bad arguments
+    3  The script failed to parse, or fmt would drop part of it
+    4  An expression evaluated to an error with --stop-on-error, or every
+       script is waiting on a channel
+    5  A tick, queue or time limit was reached, or the script would never end
+    n  The script called exit with code n, from 0 to 255. Scripts can exit
+       with 1 to 5 too, which then look like the codes above
+";
+
+const DEBUG_HELP : &str = "\
+Commands:
+    step [n], s [n]        Execute the next n expressions, 1 by default
+    continue, c            Execute until a breakpoint, a watchpoint, an error or
+                           the end of the script
+    break tick <n>         Pause before tick n
+    break len <n>          Pause when exactly n expressions are queued
+    break expr <text>      Pause when the next expression starts with text
+    break line <file>:<n>  Pause before expressions written on line n of file
+    delete <id>            Remove a breakpoint
+    watch <index>          Pause when the expression at index changes
+    unwatch <index>        Stop watching index
+    info                   List breakpoints and watchpoints
+    queue [start] [count]  Print count expressions from index start, 0 and 10
+                           by default. Index 0 is the expression executed last
+    eval <expr>            Evaluate expressions without changing the queue
+    help                   Print this message
+    quit, q                Stop debugging
+
+The script reads no input, since stdin is used for commands.
+";
+
+const EXIT_READ    : u8 = 1;
+const EXIT_USAGE   : u8 = 2;
+const EXIT_PARSE   : u8 = 3;
+const EXIT_RUNTIME : u8 = 4;
+const EXIT_LIMIT   : u8 = 5;
+
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Run,
+    Check,
+    Fmt,
+    Debug
+}
+
+struct Args {
+    command   : Command,
+    script    : Option<String>,
+    max_ticks : Option<u64>,
+    max_queue : Option<usize>,
+    max_time  : Option<Duration>,
+    jail      : Option<String>,
+    read_only : bool,
+    queue_in  : bool,
+    save      : Option<String>,
+    resume    : Option<String>,
+    record    : Option<String>,
+    trace     : bool,
+    profile   : bool,
+    stacks    : Option<String>,
+    cycles    : bool,
+    errors    : bool
+}
+
+enum ParsedArgs {
+    Args(Args),
+    Dap,
+    Help
+}
+
+
+fn parse_args(mut args : impl Iterator<Item = String>) -> Result<ParsedArgs, String> {
+    let mut command   = None;
+    let mut script    = None;
+    let mut max_ticks = None;
+    let mut max_queue = None;
+    let mut max_time  = None;
+    let mut jail      = None;
+    let mut read_only = false;
+    let mut queue_in  = false;
+    let mut save      = None;
+    let mut resume    = None;
+    let mut record    = None;
+    let mut trace     = false;
+    let mut profile   = false;
+    let mut stacks    = None;
+    let mut cycles    = false;
+    let mut errors    = false;
+    let mut dap       = false;
+    while let Some(arg) = args.next() {
+        match (arg.as_str()) {
+            "-h" | "--help" => { return Ok(ParsedArgs::Help); },
+            "--max-ticks"   => { max_ticks = Some(parse_number(&arg, args.next())?); },
+            "--max-queue"   => { max_queue = Some(parse_number(&arg, args.next())?); },
+            "--max-time"    => {
+                let secs : f64 = parse_number(&arg, args.next())?;
+                max_time = Some(Duration::try_from_secs_f64(secs).map_err(|_| format!("{} needs a positive number of seconds", arg))?);
+            },
+            "--jail"        => { jail = Some(args.next().ok_or("--jail needs a directory")?); },
+            "--read-only"   => { read_only = true; },
+            "--stdin-queue" => { queue_in = true; },
+            "--save"        => { save = Some(args.next().ok_or("--save needs a file")?); },
+            "--resume"      => { resume = Some(args.next().ok_or("--resume needs a file")?); },
+            "--record"      => { record = Some(args.next().ok_or("--record needs a file")?); },
+            "--trace"       => { trace = true; },
+            "--profile"     => { profile = true; },
+            "--stacks"      => { stacks = Some(args.next().ok_or("--stacks needs a file")?); },
+            "--detect-cycles" => { cycles = true; },
+            "--stop-on-error" => { errors = true; },
+            "run"   if (command.is_none() && script.is_none()) => { command = Some(Command::Run); },
+            "check" if (command.is_none() && script.is_none()) => { command = Some(Command::Check); },
+            "fmt"   if (command.is_none() && script.is_none()) => { command = Some(Command::Fmt); },
+            "debug" if (command.is_none() && script.is_none()) => { command = Some(Command::Debug); },
+            "dap"   if (command.is_none() && script.is_none()) => { dap = true; },
+            flag if (flag.starts_with("--") || (flag.starts_with('-') && flag != "-")) => {
+                return Err(format!("unknown option {}", flag));
+            },
+            _ if (script.is_some()) => { return Err(format!("unexpected argument {}", arg)); },
+            _                       => { script = Some(arg); }
+        }
+    }
+    let options = max_ticks.is_some() || max_queue.is_some() || max_time.is_some() || jail.is_some() || read_only || queue_in || save.is_some() || resume.is_some() || record.is_some() || trace || profile || stacks.is_some() || cycles || errors;
+    if (dap) {
+        return match (command, script, options,) {
+            (None, None, false,) => Ok(ParsedArgs::Dap),
+            _                    => Err("dap takes no arguments, its client says what to run".to_string())
+        };
+    }
+    let command = command.unwrap_or(Command::Run);
+    if (command != Command::Run && options) {
+        return Err("options other than --help only apply to run".to_string());
+    }
+    match (&script, &resume,) {
+        (None,    None,)    => { return Err("missing script".to_string()); },
+        (Some(_), Some(_),) => { return Err("--resume continues a snapshot instead of running a script".to_string()); },
+        _                   => { }
+    }
+    if (command == Command::Debug && script.as_deref() == Some("-")) {
+        return Err("debug reads commands from stdin, so the script has to come from a file".to_string());
+    }
+    if (queue_in && script.as_deref() == Some("-")) {
+        return Err("--stdin-queue needs the script to come from a file".to_string());
+    }
+    Ok(ParsedArgs::Args(Args { command, script, max_ticks, max_queue, max_time, jail, read_only, queue_in, save, resume, record, trace, profile, stacks, cycles, errors }))
+}
+
+fn parse_number<T : std::str::FromStr>(flag : &str, value : Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value.parse().map_err(|_| format!("{} needs a number, found {}", flag, value))
+}
+
+
+fn main() -> ExitCode {
+    let args = match (parse_args(env::args().skip(1))) {
+        Ok(ParsedArgs::Args(args)) => args,
+        Ok(ParsedArgs::Help)       => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        },
+        Ok(ParsedArgs::Dap)        => {
+            return match (dap::Server::new(io::stdout()).serve(io::stdin().lock())) {
+                Ok(())   => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("Failed to talk to the client: {}", err);
+                    ExitCode::from(EXIT_READ)
+                }
+            };
+        },
+        Err(err)                   => {
+            eprint!("{}\n\n{}", err, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let Some(script) = &args.script
+        else { return run(&args, Vec::new()); };
+    let (source, origin,) = match (read_script(script)) {
+        Ok(read) => read,
+        Err(err) => {
+            eprintln!("Failed to read {}: {}", script, err);
+            return ExitCode::from(EXIT_READ);
+        }
+    };
+    let exprs = match (parser::parse_with_origin(&source, origin)) {
+        Ok(exprs) => exprs,
+        Err(err)  => {
+            err.eprint(Render::detect());
+            return ExitCode::from(EXIT_PARSE);
+        }
+    };
+
+    match (args.command) {
+        Command::Check => ExitCode::SUCCESS,
+        Command::Fmt   => {
+            if let Some(lost) = dropped_by_fmt(&source, &exprs) {
+                eprintln!("Refusing to format {}, since it has {}", script, lost);
+                return ExitCode::from(EXIT_PARSE);
+            }
+            for expr in exprs { println!("{}", expr); }
+            ExitCode::SUCCESS
+        },
+        Command::Run   => run(&args, exprs),
+        Command::Debug => debug(exprs)
+    }
+}
+
+/// What printing `exprs`, which were parsed from `source`, would drop besides
+///  spacing, grouping and blank lines.
+fn dropped_by_fmt(source : &str, exprs : &[Expr]) -> Option<&'static str> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    if (source.starts_with("#!")) { return Some("a shebang line"); }
+    if (has_comment(source)) { return Some("comments"); }
+    let starts = exprs.iter().map(|expr| expr.span.line).collect::<BTreeSet<_>>();
+    source.lines().enumerate()
+        .any(|(i, line,)| ! line.trim().is_empty() && ! starts.contains(&(i + 1)))
+        .then_some("expressions continued on other lines")
+}
+
+/// Whether `source` has a comment outside of its strings.
+fn has_comment(source : &str) -> bool {
+    let mut chars  = source.chars().peekable();
+    let mut string = None;
+    while let Some(ch) = chars.next() {
+        match (string) {
+            // Strings can't span lines, so a newline ends an unterminated one.
+            Some(terminator) => match (ch) {
+                '\\'                     => { chars.next_if(|&ch| ch != '\n'); },
+                '\n'                     => { string = None; },
+                ch if (ch == terminator) => { string = None; },
+                _                        => { }
+            },
+            None             => match (ch, chars.peek(),) {
+                ('/', Some('/' | '*'),) => { return true; },
+                ('"' | '`', _,)         => { string = Some(ch); },
+                _                       => { }
+            }
+        }
+    }
+    false
+}
+
+fn read_script(script : &str) -> io::Result<(String, Origin,)> {
+    if (script == "-") {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source)?;
+        Ok((source, Origin::file("<stdin>"),))
+    } else {
+        Ok((fs::read_to_string(script)?, Origin::file(script),))
+    }
+}
+
+fn run(args : &Args, exprs : Vec<Expr>) -> ExitCode {
+    let jail = match (args.jail.as_ref().map(Jail::new).transpose()) {
+        Ok(jail) => jail,
+        Err(err) => {
+            eprintln!("Failed to open jail {}: {}", args.jail.as_deref().unwrap_or_default(), err);
+            return ExitCode::from(EXIT_READ);
+        }
+    };
+    let (read_only, queue_in,) = (args.read_only, args.queue_in,);
+    // Spawned scripts run under the same restrictions as the main one.
+    let spawner = move || {
+        let mut executor = Executor::new();
+        if let Some(jail) = &jail { executor.set_file_system(jail.clone()); }
+        if (read_only) { executor.set_file_access(Access::ReadOnly); }
+        if (queue_in) { executor.set_input(Reader::new(io::empty())); }
+        executor
+    };
+    let mut executor = spawner();
+    if (queue_in) { executor.set_queue_source(Reader::stdin()); }
+    if let Some(resume) = &args.resume {
+        let loaded = fs::File::open(resume).map_err(SnapshotError::from)
+            .and_then(|file| executor.load(file));
+        if let Err(err) = loaded {
+            eprintln!("Failed to resume from {}: {}", resume, err);
+            return ExitCode::from(EXIT_READ);
+        }
+    }
+    if let Some(record) = &args.record {
+        match (fs::File::create(record)) {
+            Ok(file) => executor.start_trace(io::BufWriter::new(file)),
+            Err(err) => {
+                eprintln!("Failed to record a trace to {}: {}", record, err);
+                return ExitCode::from(EXIT_READ);
+            }
+        }
+    }
+    if (args.profile || args.stacks.is_some()) { executor.start_profile(); }
+    executor.push_exprs(exprs);
+
+    let mut limits = Limits::new(args.max_ticks.unwrap_or(u64::MAX));
+    if let Some(max_queue) = args.max_queue { limits = limits.with_queue(max_queue); }
+    if let Some(max_time)  = args.max_time  { limits = limits.with_time(max_time); }
+    if (args.cycles) { limits = limits.with_cycle_detection(); }
+    if (args.errors) { limits = limits.with_stop_on_error(); }
+
+    let mut scheduler = Scheduler::new(executor).with_spawner(spawner);
+    let report = scheduler.run_tracing(limits, |id, tick, expr| {
+        if (! args.trace) { return; }
+        match (id) {
+            0  => eprintln!("[{}] {}: {}", tick, expr.span, expr),
+            id => eprintln!("[{}:{}] {}: {}", id, tick, expr.span, expr)
+        }
+    });
+    let spawned  = scheduler.executors().len() > 1;
+    let executor = scheduler.main_mut();
+    if let (Some(record), Err(err),) = (&args.record, executor.stop_trace(),) {
+        eprintln!("Failed to record a trace to {}: {}", record, err);
+        return ExitCode::from(EXIT_READ);
+    }
+    if let Some(profile) = executor.stop_profile() {
+        if (args.profile) { eprint!("{}", profile); }
+        if let Some(stacks) = &args.stacks {
+            let written = fs::File::create(stacks).and_then(|file| profile.write_collapsed(io::BufWriter::new(file)));
+            if let Err(err) = written {
+                eprintln!("Failed to write the profile to {}: {}", stacks, err);
+                return ExitCode::from(EXIT_READ);
+            }
+        }
+    }
+    if let Some(save) = &args.save {
+        let mut snapshot = Vec::new();
+        let saved = if (spawned) { Err(io::Error::new(io::ErrorKind::Unsupported, "snapshots can't hold channels or spawned scripts")) }
+            else { executor.save(&mut snapshot).and_then(|()| fs::write(save, snapshot)) };
+        if let Err(err) = saved {
+            eprintln!("Failed to save a snapshot to {}: {}", save, err);
+            return ExitCode::from(EXIT_READ);
+        }
+    }
+    let queued = executor.len_exprs() - 1;
+    let script = match (report.executor) {
+        0  => String::new(),
+        id => format!(" in spawned script {}", id)
+    };
+    match (report.outcome) {
+        Outcome::Completed            => ExitCode::SUCCESS,
+        // exit only takes codes from 0 to 255, but a snapshot could say anything.
+        Outcome::Halted(code)         => ExitCode::from(code.clamp(0, 255) as u8),
+        Outcome::BudgetExhausted      => {
+            eprintln!("Stopped after {} ticks with {} expressions still queued", report.ticks, queued);
+            ExitCode::from(EXIT_LIMIT)
+        },
+        Outcome::QueueLimitExceeded   => {
+            eprintln!("Stopped after {} ticks: more than {} expressions are queued{}", report.ticks, limits.queue.unwrap_or(0), script);
+            ExitCode::from(EXIT_LIMIT)
+        },
+        Outcome::TimeLimitExceeded    => {
+            eprintln!("Stopped after {} ticks and {:.3?} with {} expressions still queued", report.ticks, report.elapsed, queued);
+            ExitCode::from(EXIT_LIMIT)
+        },
+        Outcome::CycleDetected(cycle) => {
+            eprintln!("Stopped after {} ticks: the queue{} after tick {} came back after tick {}, and would repeat every {} ticks forever", report.ticks, script, cycle.first, cycle.repeat, cycle.length);
+            ExitCode::from(EXIT_LIMIT)
+        },
+        Outcome::Blocked              => {
+            eprintln!("Stopped after {} ticks: every script is waiting to receive from an empty channel", report.ticks);
+            ExitCode::from(EXIT_RUNTIME)
+        },
+        Outcome::Failed(err)          => {
+            match (&err.expr) {
+                Some(expr) => eprintln!("Error{} at {}: {}\n    in {}", script, expr.span, err, expr),
+                None       => eprintln!("Error{}: {}", script, err)
+            }
+            ExitCode::from(EXIT_RUNTIME)
+        }
+    }
+}
+
+
+fn debug(exprs : Vec<Expr>) -> ExitCode {
+    let mut executor = Executor::new().with_input(Reader::new(io::empty()));
+    executor.push_exprs(exprs);
+    let mut debugger = Debugger::new(executor);
+    print_next(&debugger);
+
+    let stdin = io::stdin();
+    loop {
+        print!("(sisyphus) ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match (stdin.read_line(&mut line)) {
+            Ok(0)    => { println!(); return ExitCode::SUCCESS; },
+            Ok(_)    => { },
+            Err(err) => {
+                eprintln!("Failed to read a command: {}", err);
+                return ExitCode::from(EXIT_READ);
+            }
+        }
+        let line = line.trim();
+        let (command, rest,) = line.split_once(' ').map_or((line, ""), |(command, rest,)| (command, rest.trim(),));
+        match (command) {
+            ""                => { },
+            "s" | "step"      => {
+                let Some(count) = number_or(rest, 1)
+                    else { println!("Expected a number of steps, found {}", rest); continue; };
+                for _ in 0..count {
+                    let ticks = debugger.executor().ticks();
+                    let pause = debugger.step();
+                    if (debugger.executor().ticks() != ticks) { print_step(&debugger); }
+                    if (pause != Pause::Step) { print_pause(&debugger, pause); break; }
+                }
+                print_next(&debugger);
+            },
+            "c" | "continue"  => {
+                let pause = debugger.resume();
+                print_pause(&debugger, pause);
+                print_next(&debugger);
+            },
+            "break"           => match (rest.parse::<Breakpoint>()) {
+                Ok(breakpoint) => {
+                    let text = breakpoint.to_string();
+                    println!("Breakpoint {}: {}", debugger.add_breakpoint(breakpoint), text);
+                },
+                Err(err)       => println!("{}", err)
+            },
+            "delete"          => match (rest.parse()) {
+                Ok(id) if (debugger.remove_breakpoint(id)) => println!("Removed breakpoint {}", id),
+                _                                          => println!("No breakpoint {}", rest)
+            },
+            "watch"           => match (rest.parse()) {
+                Ok(index) => {
+                    debugger.watch(index);
+                    println!("Watching index {}: {}", index, or_nothing(debugger.executor().get_expr(index)));
+                },
+                Err(_)    => println!("Expected an index, found {}", rest)
+            },
+            "unwatch"         => match (rest.parse()) {
+                Ok(index) if (debugger.unwatch(index)) => println!("Stopped watching index {}", index),
+                _                                      => println!("Not watching {}", rest)
+            },
+            "info"            => {
+                for (id, breakpoint,) in debugger.breakpoints() { println!("Breakpoint {}: {}", id, breakpoint); }
+                for index in debugger.watchpoints() { println!("Watching index {}", index); }
+            },
+            "queue"           => {
+                let (start, count,) = rest.split_once(' ').unwrap_or((rest, ""));
+                let (Some(start), Some(count),) = (number_or(start, 0), number_or(count, 10),)
+                    else { println!("Expected a start index and a count, found {}", rest); continue; };
+                let len = debugger.executor().len_exprs();
+                if (start >= len) { println!("Nothing at index {}, {} expressions are queued", start, len - 1); continue; }
+                for (i, expr,) in debugger.executor().get_exprs(start, (start + count).min(len)).unwrap().into_iter().enumerate() {
+                    println!("{:>4}: {}", start + i, expr);
+                }
+            },
+            "eval"            => match (parser::parse_with_origin(rest, Origin::file("<eval>"))) {
+                Ok(exprs) => for expr in exprs { println!("{}", debugger.evaluate(&expr)); },
+                Err(err)  => err.eprint(Render::detect())
+            },
+            "help"            => print!("{}", DEBUG_HELP),
+            "q" | "quit"      => { return ExitCode::SUCCESS; },
+            command           => println!("Unknown command {}, type help for the list of commands", command)
+        }
+    }
+}
+
+fn number_or(text : &str, default : usize) -> Option<usize> {
+    if (text.is_empty()) { Some(default) } else { text.trim().parse().ok() }
+}
+
+fn or_nothing(expr : Option<&Expr>) -> String {
+    expr.map_or_else(|| "(nothing)".to_string(), |expr| expr.to_string())
+}
+
+fn print_step(debugger : &Debugger) {
+    let e = debugger.executor();
+    match (debugger.last_value()) {
+        Some(value) => println!("[{}] {} => {}", e.ticks(), or_nothing(e.get_expr(0)), value),
+        None        => println!("[{}] {}", e.ticks(), or_nothing(e.get_expr(0)))
+    }
+}
+
+fn print_next(debugger : &Debugger) {
+    let e = debugger.executor();
+    if (e.halted().is_some()) { return; }
+    match (e.next_expr()) {
+        Some(expr) => println!("Next, tick {} at {}: {}", e.ticks() + 1, expr.span, expr),
+        None       => println!("The queue is empty")
+    }
+}
+
+fn print_pause(debugger : &Debugger, pause : Pause) {
+    match (pause) {
+        Pause::Step                                => { },
+        Pause::Breakpoint (id)                     => {
+            let breakpoint = debugger.breakpoints().find(|(i, _,)| *i == id).map(|(_, breakpoint,)| breakpoint.to_string());
+            println!("Breakpoint {}: {}", id, breakpoint.unwrap_or_default());
+        },
+        Pause::Watchpoint { index, before, after } => {
+            println!("Index {} changed from {} to {}", index, or_nothing(before.as_ref()), or_nothing(after.as_ref()));
+        },
+        Pause::Failed     (err)                    => match (&err.expr) {
+            Some(expr) => println!("Error at {}: {}\n    in {}", expr.span, err, expr),
+            None       => println!("Error: {}", err)
+        },
+        Pause::Completed                           => { },
+        Pause::Halted     (code)                   => println!("The script exited with code {}", code)
+    }
 }
@@ -0,0 +1,258 @@
+//! The table of builtins. The parser, the printer and the executor are all
+//!  driven from it, and its documentation mirrors `sisyphus-spec`.
+
+
+use crate::exec::{ Executor, Value };
+use crate::exec::builtins::{ self, ExecResult };
+use crate::expr::Expr;
+use crate::span::Span;
+
+
+#[derive(Clone, Copy)]
+pub(crate) enum Implementation {
+    /// Receives its arguments already evaluated, from left to right.
+    Eager(fn(&mut Executor, &Span, Vec<Value>) -> ExecResult),
+    /// Evaluates its own arguments, if at all.
+    Lazy(fn(&mut Executor, &Span, &[Expr]) -> ExecResult)
+}
+
+
+// A builtin whose last parameter is followed by `..` takes any number of
+//  arguments for it, including none.
+macro_rules! variadic {
+    ( )                          => { false };
+    ( .. )                       => { true };
+    ( $first:tt $( $rest:tt )* ) => { variadic!( $( $rest )* ) };
+}
+
+macro_rules! params {
+    ( $( $param:ident ),* $( .. )? ) => { &[ $( stringify!($param) ),* ] };
+}
+
+macro_rules! builtins {
+    ( $(
+        $( #[doc = $doc:literal] )*
+        $name:ident $keyword:literal ( $( $params:tt )* ) => $kind:ident $implementation:path;
+    )* ) => {
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum Builtin {
+            $( $( #[doc = $doc] )* $name ),*
+        }
+
+        impl Builtin {
+
+            pub const ALL : &'static [Builtin] = &[ $( Builtin::$name ),* ];
+
+            pub fn from_keyword(keyword : &str) -> Option<Self> {
+                match (keyword) {
+                    $( $keyword => Some(Self::$name), )*
+                    _ => None
+                }
+            }
+
+            pub fn keyword(self) -> &'static str {
+                match (self) { $( Self::$name => $keyword ),* }
+            }
+
+            pub fn params(self) -> &'static [&'static str] {
+                match (self) { $( Self::$name => params!( $( $params )* ) ),* }
+            }
+
+            /// Whether the last parameter takes any number of arguments.
+            pub fn variadic(self) -> bool {
+                match (self) { $( Self::$name => variadic!( $( $params )* ) ),* }
+            }
+
+            /// The number of arguments the builtin takes, or the fewest it
+            ///  takes if it is variadic.
+            pub fn arity(self) -> usize {
+                self.params().len() - (self.variadic() as usize)
+            }
+
+            pub fn doc(self) -> &'static str {
+                match (self) { $( Self::$name => concat!( $( $doc, "\n" ),* ) ),* }
+            }
+
+            pub(crate) fn implementation(self) -> Implementation {
+                match (self) { $( Self::$name => Implementation::$kind($implementation) ),* }
+            }
+
+        }
+
+    }
+}
+
+
+builtins! {
+    /// Prints the given message to the console.
+    Print         "print"   ( message )                     => Eager builtins::print;
+    /// If the given condition is true, when_true is returned, else when_false.
+    If            "if"      ( condition, when_true, when_false ) => Lazy builtins::r#if;
+
+    /// Adds two values.
+    ///
+    /// Strings are concatenated with anything, and arrays with arrays.
+    Add           "+"       ( left, right )                 => Eager builtins::add;
+    /// Subtracts the right value from the left one.
+    Sub           "-"       ( left, right )                 => Eager builtins::sub;
+    /// Multiplies two values.
+    ///
+    /// Strings and arrays are repeated when multiplied by an int.
+    Mul           "*"       ( left, right )                 => Eager builtins::mul;
+    /// Divides the left value by the right one.
+    Div           "/"       ( left, right )                 => Eager builtins::div;
+    /// Returns the remainder of dividing the left value by the right one.
+    Rem           "%"       ( left, right )                 => Eager builtins::rem;
+    /// Negates a bool or number, or reverses a string or array.
+    Not           "!"       ( value )                       => Eager builtins::not;
+
+    /// Returns whether two values are equal.
+    Equals        "="       ( left, right )                 => Eager builtins::equals;
+    /// Returns whether the left value is greater than the right one.
+    Greater       ">"       ( left, right )                 => Eager builtins::greater;
+    /// Returns whether the left value is greater than or equal to the right one.
+    GreaterEquals ">="      ( left, right )                 => Eager builtins::greater_equals;
+    /// Returns whether the left value is less than the right one.
+    Less          "<"       ( left, right )                 => Eager builtins::less;
+    /// Returns whether the left value is less than or equal to the right one.
+    LessEquals    "<="      ( left, right )                 => Eager builtins::less_equals;
+
+    /// Gets a single entry from the given array.
+    Get           "get"     ( array, line )                 => Eager builtins::get;
+    /// Gets the entries from start up to, but not including, end.
+    Gets          "gets"    ( array, start, end )           => Eager builtins::gets;
+
+    /// Pushes a single entry at the end of the given array.
+    ///
+    /// Returns the array with the entry added.
+    Push          "push"    ( array, instruction )          => Eager builtins::push;
+    /// Pushes every entry of an array at the end of the given array.
+    ///
+    /// Returns the array with the entries added.
+    Pushes        "pushes"  ( array, instructions )         => Eager builtins::pushes;
+
+    /// Inserts a single entry at some position in the given array.
+    Insert        "insert"  ( array, line, instruction )    => Eager builtins::insert;
+    /// Inserts every entry of an array, starting at some position in the given
+    ///  array.
+    Inserts       "inserts" ( array, line, instructions )   => Eager builtins::inserts;
+
+    /// Overwrites a single entry at some position in the given
+    ///  array.
+    ///
+    /// Returns the array with the entry overwritten.
+    Set           "set"     ( array, line, instruction )    => Eager builtins::set;
+    /// Overwrites consecutive entries, starting at some position in the given
+    ///  array.
+    ///
+    /// Returns the array with the entries overwritten.
+    Sets          "sets"    ( array, line, instructions )   => Eager builtins::sets;
+
+    /// Returns the number of elements in the given array.
+    Len           "len"     ( array )                       => Eager builtins::len;
+
+    /// Creates an empty queue with the given name, and returns it.
+    ///
+    /// Named queues hold expressions like the main queue, but are only executed
+    ///  once scheduled.
+    MakeQueue     "mkqueue" ( name )                        => Eager builtins::mkqueue;
+    /// Returns the queue with the given name.
+    Named         "named"   ( name )                        => Eager builtins::named_queue;
+    /// Moves count entries from the front of one queue to the end of another.
+    ///
+    /// Returns the queue the entries were moved to.
+    Transfer      "transfer" ( from, to, count )            => Eager builtins::transfer;
+    /// Sets whether a named queue is executed, taking turns with the main queue
+    ///  and every other scheduled queue.
+    ///
+    /// Returns the queue.
+    Schedule      "schedule" ( queue, scheduled )           => Eager builtins::schedule;
+
+    /// Returns the channel with the given name, creating it if there is none.
+    ///
+    /// Channels pass values between a script and the scripts it spawns.
+    Channel       "channel" ( name )                        => Eager builtins::channel;
+    /// Adds a value to the end of a channel.
+    ///
+    /// Returns the channel.
+    Send          "send"    ( channel, value )              => Eager builtins::send;
+    /// Takes the value at the front of a channel.
+    ///
+    /// If the channel is empty, the script waits until another script sends
+    ///  to it, and then carries on from the `recv`. Nothing the instruction did
+    ///  before it happens again.
+    Recv          "recv"    ( channel )                     => Eager builtins::recv;
+    /// Starts another script, running the given instruction or array of
+    ///  instructions. Scripts take turns, one instruction each, in the order
+    ///  they were started.
+    Spawn         "spawn"   ( instructions )                => Eager builtins::spawn;
+
+    /// Reads the entirety of a file as a string.
+    FSRead        "fsread"  ( fname )                       => Eager builtins::fsread;
+    /// Writes the string to a file, overwriting it.
+    FSWrite       "fswrite" ( fname, content )              => Eager builtins::fswrite;
+    /// Reads the entirety of a file as an array of bytes.
+    FBRead        "fbread"  ( fname )                       => Eager builtins::fbread;
+    /// Writes an array of bytes to a file, overwriting it.
+    FBWrite       "fbwrite" ( fname, content )              => Eager builtins::fbwrite;
+
+    /// Reads a line from the input, without its line ending.
+    ///
+    /// Returns an end_of_input error once there is nothing left to read.
+    ReadLine      "readln"  ( )                             => Eager builtins::readln;
+    /// Reads everything left in the input.
+    ReadAll       "readall" ( )                             => Eager builtins::readall;
+
+    /// Returns the ints from start up to, but not including, end.
+    Range         "range"   ( start, end )                  => Eager builtins::range;
+
+    /// Converts a value to its printed form.
+    Str           "str"     ( value )                       => Eager builtins::str;
+    /// Parses the printed form of a value as an int.
+    Int           "int"     ( value )                       => Eager builtins::int;
+
+    /// Returns the kind and message of an error as a two element array.
+    ///
+    /// Returns unit if the value is not an error.
+    Reason        "reason"  ( value )                       => Eager builtins::reason;
+
+    /// Stops the script once the current instruction finishes, with the given
+    ///  exit code, from 0 to 255.
+    Exit          "exit"    ( code )                        => Eager builtins::exit;
+
+    /// Calls a function the program running the script registered under the
+    ///  given name, with every argument after it.
+    ///
+    /// Takes every remaining argument of the expression it is in, so it has to
+    ///  be put in parentheses when another argument follows.
+    Call          "call"    ( name, args.. )                => Eager builtins::call;
+}
+
+
+/// Builtins that reach outside the script, which an embedder can disable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Group {
+    /// Reading and writing files.
+    Fs,
+    /// Printing and reading input.
+    Io,
+    /// Exiting and spawning other scripts.
+    Process
+}
+
+impl Group {
+    pub const ALL : &'static [Group] = &[Group::Fs, Group::Io, Group::Process];
+}
+
+impl Builtin {
+    /// The group the builtin belongs to, if it reaches outside the script.
+    pub fn group(self) -> Option<Group> {
+        match (self) {
+            Self::FSRead | Self::FSWrite | Self::FBRead | Self::FBWrite => Some(Group::Fs),
+            Self::Print | Self::ReadLine | Self::ReadAll                => Some(Group::Io),
+            Self::Exit | Self::Spawn                                    => Some(Group::Process),
+            _                                                           => None
+        }
+    }
+}
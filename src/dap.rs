@@ -0,0 +1,258 @@
+//! A [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/)
+//!  server, so editors can drive a [`Debugger`].
+//!
+//! There is one thread, whose single stack frame is the next expression in the
+//!  queue, with a `queue` scope listing every expression still waiting. The
+//!  script's output is sent as `output` events, and it reads no input, since
+//!  stdin is usually the transport.
+
+
+use crate::debug::{ Breakpoint, Debugger, Pause };
+use crate::exec::{ Capture, Executor, Reader, Value };
+use crate::parser;
+use crate::span::Origin;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{ self, BufRead, ErrorKind, Write };
+use std::path::{ Path, PathBuf };
+use serde_json::{ json, Value as Json };
+
+
+const THREAD : i64 = 1;
+const QUEUE  : i64 = 1;
+
+
+/// Reads one message, with its `Content-Length` header. Returns `None` at the
+///  end of the input.
+pub fn read_message(reader : &mut impl BufRead) -> io::Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if (reader.read_line(&mut line)? == 0) { return Ok(None); }
+        let line = line.trim_end();
+        if (line.is_empty()) { break; }
+        if let Some((name, value,)) = line.split_once(':') && name.eq_ignore_ascii_case("Content-Length") {
+            length = Some(value.trim().parse().map_err(|_| io::Error::new(ErrorKind::InvalidData, "bad Content-Length"))?);
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body).map(Some).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+}
+
+/// Writes one message, with its `Content-Length` header.
+pub fn write_message(writer : &mut impl Write, message : &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+
+struct Program {
+    debugger : Debugger,
+    output   : Capture,
+    /// The name expressions read from the program were parsed with.
+    name     : String,
+    path     : PathBuf,
+    /// Lines that initially-loaded expressions start on.
+    lines    : HashSet<usize>
+}
+
+pub struct Server<W> {
+    writer        : W,
+    seq           : i64,
+    program       : Option<Program>,
+    stop_on_entry : bool
+}
+
+impl<W : Write> Server<W> {
+
+    pub fn new(writer : W) -> Self {
+        Self { writer, seq : 0, program : None, stop_on_entry : false }
+    }
+
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+
+    /// Handles requests until the client disconnects or the input ends.
+    pub fn serve(&mut self, mut reader : impl BufRead) -> io::Result<()> {
+        while let Some(request) = read_message(&mut reader)? {
+            if (request["type"] != "request") { continue; }
+            let command = request["command"].as_str().unwrap_or("").to_string();
+            let args    = &request["arguments"];
+            let result  = match (command.as_str()) {
+                "initialize"                                => Ok(json!({ "supportsConfigurationDoneRequest" : true })),
+                "launch"                                    => self.launch(args),
+                "setBreakpoints"                            => self.set_breakpoints(args),
+                "configurationDone"                         => Ok(Json::Null),
+                "threads"                                   => Ok(json!({ "threads" : [{ "id" : THREAD, "name" : "queue" }] })),
+                "stackTrace"                                => self.stack_trace(),
+                "scopes"                                    => Ok(json!({ "scopes" : [{ "name" : "queue", "variablesReference" : QUEUE, "expensive" : false }] })),
+                "variables"                                 => self.variables(args),
+                "evaluate"                                  => self.evaluate(args),
+                "next" | "stepIn" | "stepOut" | "continue"  => self.program.as_ref().map(|_| Json::Null).ok_or_else(not_launched),
+                "disconnect" | "terminate"                  => Ok(Json::Null),
+                command                                     => Err(format!("unsupported request {}", command))
+            };
+            self.respond(&request, &command, result)?;
+
+            // Events that follow from a request are sent after its response.
+            match (command.as_str()) {
+                "launch" if (self.program.is_some())   => { self.event("initialized", Json::Null)?; },
+                "configurationDone"                    => {
+                    if (self.stop_on_entry) { self.stopped("entry", json!({}))?; }
+                    else { self.run(Debugger::resume)?; }
+                },
+                "next" | "stepIn" | "stepOut"          => { self.run(Debugger::step)?; },
+                "continue"                             => { self.run(Debugger::resume)?; },
+                "disconnect" | "terminate"             => { return Ok(()); },
+                _                                      => { }
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, mut message : Json) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.writer, &message)
+    }
+
+    fn respond(&mut self, request : &Json, command : &str, result : Result<Json, String>) -> io::Result<()> {
+        let mut response = json!({ "type" : "response", "request_seq" : request["seq"], "command" : command });
+        match (result) {
+            Ok(Json::Null) => { response["success"] = json!(true); },
+            Ok(body)       => { response["success"] = json!(true); response["body"] = body; },
+            Err(message)   => { response["success"] = json!(false); response["message"] = json!(message); }
+        }
+        self.send(response)
+    }
+
+    fn event(&mut self, event : &str, body : Json) -> io::Result<()> {
+        let mut message = json!({ "type" : "event", "event" : event });
+        if (! body.is_null()) { message["body"] = body; }
+        self.send(message)
+    }
+
+    fn stopped(&mut self, reason : &str, mut body : Json) -> io::Result<()> {
+        body["reason"]            = json!(reason);
+        body["threadId"]          = json!(THREAD);
+        body["allThreadsStopped"] = json!(true);
+        self.event("stopped", body)
+    }
+
+    fn launch(&mut self, args : &Json) -> Result<Json, String> {
+        let name   = args["program"].as_str().ok_or("launch needs a program")?.to_string();
+        let source = fs::read_to_string(&name).map_err(|err| format!("cannot read {}: {}", name, err))?;
+        let exprs  = parser::parse_with_origin(&source, Origin::file(name.as_str())).map_err(|err| err.to_string())?;
+        let lines  = exprs.iter().map(|expr| expr.span.line).collect();
+
+        let output = Capture::new();
+        let mut executor = Executor::new()
+            .with_output(output.clone())
+            .with_input(Reader::new(io::empty()));
+        executor.push_exprs(exprs);
+        self.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+        self.program = Some(Program { debugger : Debugger::new(executor), output, path : canonical(&name), name, lines });
+        Ok(Json::Null)
+    }
+
+    fn set_breakpoints(&mut self, args : &Json) -> Result<Json, String> {
+        let program = self.program.as_mut().ok_or_else(not_launched)?;
+        let path    = args["source"]["path"].as_str().ok_or("setBreakpoints needs a source path")?;
+        let lines   = args["breakpoints"].as_array().map_or(&[][..], Vec::as_slice).iter()
+            .filter_map(|breakpoint| breakpoint["line"].as_u64())
+            .map(|line| line as usize);
+
+        // Every request replaces all the breakpoints in that source, and there
+        //  is only one source.
+        let ids = program.debugger.breakpoints().map(|(id, _,)| id).collect::<Vec<_>>();
+        for id in ids { program.debugger.remove_breakpoint(id); }
+        let mut breakpoints = Vec::new();
+        for line in lines {
+            if (canonical(path) != program.path) {
+                breakpoints.push(json!({ "verified" : false, "line" : line, "message" : "not the launched program" }));
+                continue;
+            }
+            let id = program.debugger.add_breakpoint(Breakpoint::Line { file : program.name.clone(), line });
+            let verified = program.lines.contains(&line);
+            breakpoints.push(json!({ "id" : id, "verified" : verified, "line" : line }));
+        }
+        Ok(json!({ "breakpoints" : breakpoints }))
+    }
+
+    fn stack_trace(&self) -> Result<Json, String> {
+        let program = self.program.as_ref().ok_or_else(not_launched)?;
+        let e = program.debugger.executor();
+        let mut frame = json!({ "id" : 1, "name" : "(the queue is empty)", "line" : 0, "column" : 0 });
+        if let Some(expr) = e.next_expr() {
+            frame["name"] = json!(expr.to_string());
+            let span = expr.span.root();
+            if let Origin::File(name) = &span.origin && **name == *program.name {
+                frame["source"] = json!({ "name" : file_name(&program.name), "path" : program.path });
+                frame["line"]   = json!(span.line);
+                frame["column"] = json!(span.column);
+            }
+        }
+        Ok(json!({ "stackFrames" : [frame], "totalFrames" : 1 }))
+    }
+
+    fn variables(&self, args : &Json) -> Result<Json, String> {
+        let program = self.program.as_ref().ok_or_else(not_launched)?;
+        if (args["variablesReference"] != QUEUE) { return Ok(json!({ "variables" : [] })); }
+        let e     = program.debugger.executor();
+        let start = 1 + args["start"].as_u64().unwrap_or(0) as usize;
+        let end   = args["count"].as_u64().map_or(e.len_exprs(), |count| start + count as usize).min(e.len_exprs());
+        let variables = e.get_exprs(start, end.max(start)).unwrap_or_default().into_iter().enumerate()
+            .map(|(i, expr,)| json!({ "name" : (start + i).to_string(), "value" : expr.to_string(), "variablesReference" : 0 }))
+            .collect::<Vec<_>>();
+        Ok(json!({ "variables" : variables }))
+    }
+
+    fn evaluate(&mut self, args : &Json) -> Result<Json, String> {
+        let program = self.program.as_mut().ok_or_else(not_launched)?;
+        let text    = args["expression"].as_str().ok_or("evaluate needs an expression")?;
+        let exprs   = parser::parse_with_origin(text, Origin::file("<eval>")).map_err(|err| err.to_string())?;
+        let mut result = Value::Unit;
+        for expr in exprs { result = program.debugger.evaluate(&expr); }
+        Ok(json!({ "result" : result.to_string(), "variablesReference" : 0 }))
+    }
+
+    /// Steps or resumes the program, then reports its output and why it
+    ///  stopped.
+    fn run(&mut self, how : fn(&mut Debugger) -> Pause) -> io::Result<()> {
+        let Some(program) = &mut self.program
+            else { return Ok(()); };
+        let pause  = how(&mut program.debugger);
+        let output = program.output.take();
+        if (! output.is_empty()) { self.event("output", json!({ "category" : "stdout", "output" : output }))?; }
+        match (pause) {
+            Pause::Step                                => self.stopped("step", json!({})),
+            Pause::Breakpoint (id)                     => self.stopped("breakpoint", json!({ "hitBreakpointIds" : [id] })),
+            Pause::Watchpoint { index, .. }            => self.stopped("data breakpoint", json!({ "description" : format!("index {} changed", index) })),
+            Pause::Failed     (err)                    => self.stopped("exception", json!({ "text" : err.to_string() })),
+            Pause::Completed                           => self.exited(0),
+            Pause::Halted     (code)                   => self.exited(code)
+        }
+    }
+
+    fn exited(&mut self, code : i32) -> io::Result<()> {
+        self.event("exited", json!({ "exitCode" : code }))?;
+        self.event("terminated", Json::Null)
+    }
+
+}
+
+fn not_launched() -> String {
+    "no program has been launched".to_string()
+}
+
+fn canonical(path : &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+fn file_name(path : &str) -> String {
+    Path::new(path).file_name().map_or_else(|| path.to_string(), |name| name.to_string_lossy().into_owned())
+}
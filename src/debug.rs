@@ -0,0 +1,215 @@
+//! Pausing an [`Executor`] between ticks, to step through the script and look
+//!  at its queue.
+
+
+use crate::expr::Expr;
+use crate::exec::{ Executor, Value, RuntimeError };
+use crate::span::Origin;
+use core::fmt;
+use core::str::FromStr;
+
+
+/// Where [`Debugger::resume`] should stop, checked before each tick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Before the given tick is executed.
+    Tick(u64),
+    /// When exactly this many expressions are waiting in the queue.
+    QueueLength(usize),
+    /// When the next expression, in its printed form, starts with this text.
+    Pattern(String),
+    /// When the next expression was written on this line of a file. Doesn't
+    ///  match expressions that were pushed at runtime.
+    Line { file : String, line : usize }
+}
+
+impl Breakpoint {
+    pub fn is_hit(&self, executor : &Executor) -> bool {
+        match (self) {
+            Self::Tick        (tick)    => executor.ticks() + 1 == *tick,
+            Self::QueueLength (len)     => executor.len_exprs() - 1 == *len,
+            Self::Pattern     (pattern) => executor.next_expr().is_some_and(|expr| expr.to_string().starts_with(pattern.as_str())),
+            Self::Line { file, line }   => executor.next_expr().is_some_and(|expr| {
+                expr.span.line == *line && matches!(&expr.span.origin, Origin::File(name) if (**name == **file))
+            })
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self) {
+            Self::Tick        (tick)    => write!(f, "tick {}", tick),
+            Self::QueueLength (len)     => write!(f, "len {}", len),
+            Self::Pattern     (pattern) => write!(f, "expr {}", pattern),
+            Self::Line { file, line }   => write!(f, "line {}:{}", file, line)
+        }
+    }
+}
+
+/// Parses the printed form: `tick <n>`, `len <n>`, `expr <text>` or
+///  `line <file>:<n>`.
+impl FromStr for Breakpoint {
+    type Err = String;
+    fn from_str(text : &str) -> Result<Self, Self::Err> {
+        let (kind, rest,) = text.trim_start().split_once(' ').unwrap_or((text.trim(), ""));
+        let number = || rest.trim().parse().map_err(|_| format!("expected a number, found {:?}", rest.trim()));
+        match (kind) {
+            "tick"                        => Ok(Self::Tick(number()?)),
+            "len"                         => Ok(Self::QueueLength(number()? as usize)),
+            "expr" if (! rest.is_empty()) => Ok(Self::Pattern(rest.to_string())),
+            "expr"                        => Err("expected the start of an expression".to_string()),
+            "line"                        => {
+                let (file, line,) = rest.rsplit_once(':').ok_or("expected a file and a line, like script.push:3")?;
+                let line = line.parse().map_err(|_| format!("expected a line number, found {:?}", line))?;
+                Ok(Self::Line { file : file.to_string(), line })
+            },
+            kind                          => Err(format!("unknown breakpoint {:?}, expected tick, len, expr or line", kind))
+        }
+    }
+}
+
+
+/// Why the debugger paused.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pause {
+    /// One expression was executed, by [`Debugger::step`].
+    Step,
+    /// The breakpoint with this id was hit.
+    Breakpoint(usize),
+    /// The expression at a watched index of the queue changed.
+    Watchpoint { index : usize, before : Option<Expr>, after : Option<Expr> },
+    /// The expression executed last evaluated to an error.
+    Failed(Box<RuntimeError>),
+    /// The queue is empty.
+    Completed,
+    /// The script called `exit` with the given code.
+    Halted(i32)
+}
+
+
+struct Watchpoint {
+    index : usize,
+    last  : Option<Expr>
+}
+
+/// Wraps an executor, running it a tick at a time and pausing on breakpoints
+///  and watchpoints.
+pub struct Debugger {
+    executor    : Executor,
+    breakpoints : Vec<Option<Breakpoint>>,
+    watchpoints : Vec<Watchpoint>,
+    last_value  : Option<Value>,
+    // Whether anything has been stepped or resumed yet.
+    started     : bool
+}
+
+impl Debugger {
+
+    pub fn new(executor : Executor) -> Self {
+        Self { executor, breakpoints : Vec::new(), watchpoints : Vec::new(), last_value : None, started : false }
+    }
+
+    pub fn executor(&self) -> &Executor {
+        &self.executor
+    }
+
+    pub fn executor_mut(&mut self) -> &mut Executor {
+        &mut self.executor
+    }
+
+    pub fn into_executor(self) -> Executor {
+        self.executor
+    }
+
+    /// What the expression executed last evaluated to, if the debugger has
+    ///  executed anything yet.
+    pub fn last_value(&self) -> Option<&Value> {
+        self.last_value.as_ref()
+    }
+
+    /// Adds a breakpoint, returning its id.
+    pub fn add_breakpoint(&mut self, breakpoint : Breakpoint) -> usize {
+        self.breakpoints.push(Some(breakpoint));
+        self.breakpoints.len() - 1
+    }
+
+    /// Returns `false` if there was no breakpoint with this id.
+    pub fn remove_breakpoint(&mut self, id : usize) -> bool {
+        self.breakpoints.get_mut(id).and_then(Option::take).is_some()
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = (usize, &Breakpoint)> {
+        self.breakpoints.iter().enumerate().filter_map(|(id, breakpoint,)| Some((id, breakpoint.as_ref()?,)))
+    }
+
+    /// Pauses whenever the expression at `index` changes, with indices as in
+    ///  [`Executor::get_expr`].
+    pub fn watch(&mut self, index : usize) {
+        if (self.watchpoints.iter().any(|watch| watch.index == index)) { return; }
+        let last = self.executor.get_expr(index).cloned();
+        self.watchpoints.push(Watchpoint { index, last });
+    }
+
+    /// Returns `false` if `index` wasn't being watched.
+    pub fn unwatch(&mut self, index : usize) -> bool {
+        let len = self.watchpoints.len();
+        self.watchpoints.retain(|watch| watch.index != index);
+        self.watchpoints.len() != len
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = usize> {
+        self.watchpoints.iter().map(|watch| watch.index)
+    }
+
+    /// Executes one expression. Watchpoints are checked afterwards, but
+    ///  breakpoints are not.
+    pub fn step(&mut self) -> Pause {
+        self.started = true;
+        if let Some(code) = self.executor.halted() { return Pause::Halted(code); }
+        let Some(value) = self.executor.step()
+            else { return Pause::Completed; };
+        self.last_value = Some(value);
+
+        let mut changed = None;
+        for watch in &mut self.watchpoints {
+            let now = self.executor.get_expr(watch.index).cloned();
+            if (now != watch.last) {
+                let before = std::mem::replace(&mut watch.last, now.clone());
+                changed.get_or_insert(Pause::Watchpoint { index : watch.index, before, after : now });
+            }
+        }
+        match (&self.last_value, self.executor.halted(),) {
+            (_, Some(code),)                 => Pause::Halted(code),
+            (Some(Value::Error(err)), None,) => Pause::Failed(err.clone()),
+            _                                => changed.unwrap_or(Pause::Step)
+        }
+    }
+
+    /// Executes expressions until a breakpoint or watchpoint is hit, an
+    ///  expression evaluates to an error, or the script ends. Executes at least
+    ///  one expression, so it can continue from a breakpoint, unless nothing
+    ///  has been executed yet and a breakpoint is hit before the first tick.
+    pub fn resume(&mut self) -> Pause {
+        if (! self.started) && let Some(id) = self.hit_breakpoint() {
+            self.started = true;
+            return Pause::Breakpoint(id);
+        }
+        loop {
+            let pause = self.step();
+            if (pause != Pause::Step) { return pause; }
+            if let Some(id) = self.hit_breakpoint() { return Pause::Breakpoint(id); }
+        }
+    }
+
+    fn hit_breakpoint(&self) -> Option<usize> {
+        self.breakpoints().find(|(_, breakpoint,)| breakpoint.is_hit(&self.executor)).map(|(id, _,)| id)
+    }
+
+    /// Evaluates `expr` against the paused executor, without changing its
+    ///  queue. See [`Executor::evaluate`].
+    pub fn evaluate(&mut self, expr : &Expr) -> Value {
+        self.executor.evaluate(expr)
+    }
+
+}
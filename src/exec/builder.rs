@@ -0,0 +1,131 @@
+//! Everything an embedder can configure about an [`Executor`], in one place.
+
+
+use crate::builtin::Group;
+use crate::exec::{ Executor, Input, Limits, Observer, Overflow, Value };
+use crate::exec::vfs::{ Access, FileSystem };
+use std::io::Write;
+
+
+/// Builds an [`Executor`], starting from the same defaults as
+///  [`Executor::new`]: stdout and stdin, straight to disk, no limits, errors on
+///  overflow and every builtin group enabled.
+pub struct ExecutorBuilder {
+    executor : Executor
+}
+
+impl Executor {
+    pub fn builder() -> ExecutorBuilder {
+        ExecutorBuilder::new()
+    }
+}
+
+
+impl Default for ExecutorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutorBuilder {
+
+    pub fn new() -> Self {
+        Self { executor : Executor::new() }
+    }
+
+    /// Where the script prints to.
+    pub fn output(mut self, output : impl Write + Send + 'static) -> Self {
+        self.executor.set_output(output);
+        self
+    }
+
+    /// Where `readln` and `readall` read from.
+    pub fn input(mut self, input : impl Input + 'static) -> Self {
+        self.executor.set_input(input);
+        self
+    }
+
+    /// Adds each line of `source` to the end of the queue, like
+    ///  [`Executor::with_queue_source`].
+    pub fn queue_source(mut self, source : impl Input + 'static) -> Self {
+        self.executor.set_queue_source(source);
+        self
+    }
+
+    /// Where the script reads and writes files.
+    pub fn file_system(mut self, file_system : impl FileSystem + 'static) -> Self {
+        self.executor.set_file_system(file_system);
+        self
+    }
+
+    pub fn file_access(mut self, access : Access) -> Self {
+        self.executor.set_file_access(access);
+        self
+    }
+
+    /// The limits [`Executor::run_source`] runs with.
+    pub fn limits(mut self, limits : Limits) -> Self {
+        self.executor.set_limits(limits);
+        self
+    }
+
+    pub fn max_ticks(mut self, ticks : u64) -> Self {
+        self.executor.limits.ticks = ticks;
+        self
+    }
+
+    pub fn max_queue(mut self, queue : usize) -> Self {
+        self.executor.limits.queue = Some(queue);
+        self
+    }
+
+    /// Has [`Executor::run_source`] stop once an expression evaluates to an
+    ///  error, like [`Limits::with_stop_on_error`].
+    pub fn stop_on_error(mut self) -> Self {
+        self.executor.limits.errors = true;
+        self
+    }
+
+    pub fn overflow(mut self, overflow : Overflow) -> Self {
+        self.executor.set_overflow(overflow);
+        self
+    }
+
+    pub fn enable(mut self, group : Group) -> Self {
+        self.executor.set_group_enabled(group, true);
+        self
+    }
+
+    pub fn disable(mut self, group : Group) -> Self {
+        self.executor.set_group_enabled(group, false);
+        self
+    }
+
+    /// Enables exactly the given groups, disabling every other one.
+    pub fn groups(mut self, groups : &[Group]) -> Self {
+        for &group in Group::ALL {
+            self.executor.set_group_enabled(group, groups.contains(&group));
+        }
+        self
+    }
+
+    /// Registers a function for scripts to `call`, like
+    ///  [`Executor::register`].
+    pub fn native<F>(mut self, name : impl Into<String>, arity : usize, function : F) -> Self
+    where
+        F : Fn(&mut Executor, Vec<Value>) -> Value + Send + Sync + 'static
+    {
+        self.executor.register(name, arity, function);
+        self
+    }
+
+    pub fn observer(mut self, observer : impl Observer + 'static) -> Self {
+        self.executor.add_observer(observer);
+        self
+    }
+
+    pub fn build(self) -> Executor {
+        self.executor
+    }
+
+}
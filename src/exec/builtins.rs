@@ -0,0 +1,632 @@
+//! The implementations behind [`Builtin`](crate::builtin::Builtin). Each one
+//!  is given its arguments in the order they are written, with exactly as many
+//!  as the builtin's arity.
+
+
+use crate::expr::Expr;
+use crate::exec::{ Execute, Executor, Value, RuntimeError, ErrorKind, Rope, is_queue_name };
+use crate::exec::vfs::Access;
+use crate::iter::IteratorExt;
+use crate::parser;
+use crate::span::{ Span, Origin };
+use std::io;
+
+
+pub(crate) type ExecResult = Result<Value, Box<RuntimeError>>;
+
+
+fn take<const N : usize>(args : Vec<Value>) -> [Value; N] {
+    args.try_into().expect("arity is checked before dispatch")
+}
+
+fn index(i : Value) -> Result<usize, Box<RuntimeError>> {
+    match (i) {
+        Value::Int(i) if (i >= 0) => Ok(i as usize),
+        Value::Int(i)             => Err(RuntimeError::new(ErrorKind::OutOfRange, format!("index {} is negative", i))
+            .with_operands([Value::Int(i)]).into()),
+        Value::Error(err)         => Err(err),
+        i                         => Err(RuntimeError::type_mismatch("int index", i).into())
+    }
+}
+
+fn array(v : Value) -> Result<Vec<Value>, Box<RuntimeError>> {
+    match (v) {
+        Value::Array(v)   => Ok(v),
+        Value::Error(err) => Err(err),
+        v                 => Err(RuntimeError::type_mismatch("array", v).into())
+    }
+}
+
+fn parse(e : &mut Executor, span : &Span, v : Value) -> Result<Vec<Expr>, Box<RuntimeError>> {
+    let text   = v.to_string();
+    let origin = Origin::pushed(span, e.ticks());
+    e.profile_expr(None, |_| parser::parse_with_origin(&text, origin))
+        .map_err(|_| RuntimeError::new(ErrorKind::Syntax, format!("cannot parse {:?}", text)).with_operands([v]).into())
+}
+
+
+pub(crate) fn print(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [v] = take(args);
+    writeln!(e.output(), "{}", v)
+        .map_err(|err| RuntimeError::new(ErrorKind::Io, format!("cannot print: {}", err)).with_operands([v.clone()]))?;
+    e.observe(|o| o.on_print(&v));
+    Ok(v)
+}
+
+pub(crate) fn r#if(e : &mut Executor, _span : &Span, args : &[Expr]) -> ExecResult {
+    let [c, when_true, when_false] = args else { unreachable!("arity is checked before dispatch") };
+    let c = c.execute(e);
+    let branch = match (&c) {
+        _ if (e.parked.is_some()) => { e.unwind(Vec::new()); return Ok(Value::Unit); },
+        Value::Bool(true)         => when_true,
+        Value::Bool(false)        => when_false,
+        Value::Error(err)         => { return Err(err.clone()); },
+        _                         => { return Err(RuntimeError::type_mismatch("bool", c).into()); }
+    };
+    let value = branch.execute(e);
+    // Picks the same branch again once unparked.
+    if (e.parked.is_some()) { e.unwind(vec![c]); }
+    Ok(value)
+}
+
+
+pub(crate) fn add(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [l, r] = take(args);
+    Ok(l.add_with(r, e.overflow()))
+}
+
+pub(crate) fn sub(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [l, r] = take(args);
+    Ok(l.sub_with(r, e.overflow()))
+}
+
+pub(crate) fn mul(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [l, r] = take(args);
+    Ok(l.mul_with(r, e.overflow()))
+}
+
+pub(crate) fn div(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [l, r] = take(args);
+    Ok(l.div_with(r, e.overflow()))
+}
+
+pub(crate) fn rem(_e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [l, r] = take(args);
+    Ok(l % r)
+}
+
+pub(crate) fn not(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [v] = take(args);
+    Ok(v.not_with(e.overflow()))
+}
+
+
+pub(crate) fn equals(_e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [l, r] = take(args);
+    Ok(Value::Bool(l == r))
+}
+
+pub(crate) fn greater(_e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [l, r] = take(args);
+    Ok(Value::Bool(l > r))
+}
+
+pub(crate) fn greater_equals(_e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [l, r] = take(args);
+    Ok(Value::Bool(l >= r))
+}
+
+pub(crate) fn less(_e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [l, r] = take(args);
+    Ok(Value::Bool(l < r))
+}
+
+pub(crate) fn less_equals(_e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [l, r] = take(args);
+    Ok(Value::Bool(l <= r))
+}
+
+
+pub(crate) fn get(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [q, i] = take(args);
+    let i = index(i)?;
+    match (q) {
+        Value::String    (v) => v.chars().nth(i).map(|ch| Value::String(ch.to_string()))
+                                    .ok_or_else(|| RuntimeError::out_of_range(i as i128, v.chars().count()).into()),
+        Value::ExprQueue     => e.get_expr(i).map(|v| Value::String(v.to_string()))
+                                    .ok_or_else(|| RuntimeError::out_of_range(i as i128, e.len_exprs()).into()),
+        Value::NamedQueue(n) => {
+            let q = named(e, &n)?;
+            q.get(i).map(|v| Value::String(v.to_string()))
+                .ok_or_else(|| RuntimeError::out_of_range(i as i128, q.len()).into())
+        },
+        Value::Array     (q) => q.get(i).cloned()
+                                    .ok_or_else(|| RuntimeError::out_of_range(i as i128, q.len()).into()),
+        Value::Error   (err) => Err(err),
+        q                    => Err(RuntimeError::type_mismatch("string, array or queue", q).into())
+    }
+}
+
+pub(crate) fn gets(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [q, i0, i1] = take(args);
+    let i0 = index(i0)?;
+    let i1 = index(i1)?;
+    if (i1 < i0) {
+        return Err(RuntimeError::new(ErrorKind::OutOfRange, format!("range {}..{} ends before it starts", i0, i1))
+            .with_operands([Value::Int(i0 as i128), Value::Int(i1 as i128)]).into());
+    }
+    match (q) {
+        Value::String    (v) => v.chars()
+                                    .map(|ch| Value::String(ch.to_string()))
+                                    .skip(i0).next_n_exact(i1 - i0)
+                                    .map(Value::Array)
+                                    .ok_or_else(|| RuntimeError::out_of_range(i1 as i128, v.chars().count()).into()),
+        Value::ExprQueue     => e.get_exprs_values(i0, i1)
+                                    .map(Value::Array)
+                                    .ok_or_else(|| RuntimeError::out_of_range(i1 as i128, e.len_exprs()).into()),
+        Value::NamedQueue(n) => {
+            let q = named(e, &n)?;
+            if (i1 > q.len()) { return Err(RuntimeError::out_of_range(i1 as i128, q.len()).into()); }
+            Ok(Value::Array(q.range(i0..i1).map(|v| Value::String(v.to_string())).collect()))
+        },
+        Value::Array     (q) => q.get(i0..i1)
+                                    .map(|v| Value::Array(v.to_vec()))
+                                    .ok_or_else(|| RuntimeError::out_of_range(i1 as i128, q.len()).into()),
+        Value::Error   (err) => Err(err),
+        q                    => Err(RuntimeError::type_mismatch("string, array or queue", q).into())
+    }
+}
+
+
+// Returns the resulting array/string/queue
+pub(crate) fn push(e : &mut Executor, span : &Span, args : Vec<Value>) -> ExecResult {
+    let [q, v] = take(args);
+    match (q) {
+        Value::String(str)     => Ok(Value::String(str + &v.to_string())),
+        Value::Array(mut arr)  => { arr.push(v); Ok(Value::Array(arr)) },
+        Value::ExprQueue       => {
+            let parsed_val = parse(e, span, v)?;
+            e.push_exprs(parsed_val);
+            Ok(Value::ExprQueue)
+        },
+        Value::NamedQueue(name) => {
+            let parsed_val = parse(e, span, v)?;
+            e.push_named(&name, parsed_val).map_err(|_| unknown_queue(&name))?;
+            Ok(Value::NamedQueue(name))
+        },
+        Value::Error(err)      => Err(err),
+        q                      => Err(RuntimeError::type_mismatch("string, array or queue", q).into())
+    }
+}
+
+pub(crate) fn pushes(e : &mut Executor, span : &Span, args : Vec<Value>) -> ExecResult {
+    let [q, v] = take(args);
+    let mut v = array(v)?;
+    match (q) {
+        Value::String(mut str) => {
+            for val in v {
+                str += &val.to_string();
+            }
+            Ok(Value::String(str))
+        },
+        Value::Array(mut arr)  => { arr.append(&mut v); Ok(Value::Array(arr)) },
+        Value::ExprQueue       => {
+            let parsed_vals = v.into_iter()
+                .map(|v| parse(e, span, v))
+                .collect::<Result<Vec<_>, _>>()?;
+            e.push_exprs(parsed_vals.into_iter().flatten());
+            Ok(Value::ExprQueue)
+        },
+        Value::NamedQueue(name) => {
+            let parsed_vals = v.into_iter()
+                .map(|v| parse(e, span, v))
+                .collect::<Result<Vec<_>, _>>()?;
+            e.push_named(&name, parsed_vals.into_iter().flatten()).map_err(|_| unknown_queue(&name))?;
+            Ok(Value::NamedQueue(name))
+        },
+        Value::Error(err)      => Err(err),
+        q                      => Err(RuntimeError::type_mismatch("string, array or queue", q).into())
+    }
+}
+
+
+pub(crate) fn insert(e : &mut Executor, span : &Span, args : Vec<Value>) -> ExecResult {
+    let [q, i, v] = take(args);
+    insert_at(e, span, q, index(i)?, v)
+}
+
+pub(crate) fn inserts(e : &mut Executor, span : &Span, args : Vec<Value>) -> ExecResult {
+    let [mut q, start_index, v] = take(args);
+    let v           = array(v)?;
+    let start_index = index(start_index)?;
+    for (i, val,) in v.into_iter().enumerate() {
+        q = insert_at(e, span, q, start_index + i, val)?;
+    }
+    Ok(q)
+}
+
+fn insert_at(e : &mut Executor, span : &Span, q : Value, i : usize, v : Value) -> ExecResult {
+    match (q) {
+        Value::String  (str) => {
+            let v = match (v) {
+                Value::String(v) => v,
+                v                => { return Err(RuntimeError::type_mismatch("string", v).into()); }
+            };
+            let (Some(first_slice), Some(second_slice)) = (str.get(..i), str.get(i..))
+                else { return Err(RuntimeError::out_of_range(i as i128, str.len()).into()); };
+            Ok(Value::String(String::from(first_slice) + &v + second_slice))
+        },
+        Value::ExprQueue     => {
+            let parsed_val = parse(e, span, v)?;
+            match (e.inserts_expr(i, parsed_val)) {
+                Ok(_)  => Ok(Value::ExprQueue),
+                Err(_) => Err(RuntimeError::out_of_range(i as i128, e.len_exprs()).into())
+            }
+        },
+        Value::NamedQueue(name) => {
+            let len        = named(e, &name)?.len();
+            let parsed_val = parse(e, span, v)?;
+            for (k, expr,) in parsed_val.into_iter().enumerate() {
+                e.insert_named(&name, i + k, expr).map_err(|_| RuntimeError::out_of_range(i as i128, len))?;
+            }
+            Ok(Value::NamedQueue(name))
+        },
+        Value::Array   (mut arr) => {
+            if (i > arr.len()) { return Err(RuntimeError::out_of_range(i as i128, arr.len()).into()); }
+            arr.insert(i, v);
+            Ok(Value::Array(arr))
+        },
+        Value::Error   (err) => Err(err),
+        q                    => Err(RuntimeError::type_mismatch("string, array or queue", q).into())
+    }
+}
+
+
+pub(crate) fn set(e : &mut Executor, span : &Span, args : Vec<Value>) -> ExecResult {
+    let [q, i, v] = take(args);
+    set_at(e, span, q, index(i)?, v)
+}
+
+// q is an array, start_index is an int, and v is an array
+pub(crate) fn sets(e : &mut Executor, span : &Span, args : Vec<Value>) -> ExecResult {
+    let [mut q, start_index, v] = take(args);
+    let v           = array(v)?;
+    let start_index = index(start_index)?;
+    for (i, val,) in v.into_iter().enumerate() {
+        q = set_at(e, span, q, start_index + i, val)?;
+    }
+    Ok(q)
+}
+
+fn set_at(e : &mut Executor, span : &Span, q : Value, i : usize, v : Value) -> ExecResult {
+    match (q) {
+        Value::String  (str) => {
+            let v = match (v) {
+                Value::String(v) if (v.len() == 1) => v,
+                v                                  => { return Err(RuntimeError::type_mismatch("single character string", v).into()); }
+            };
+            let (Some(first_slice), Some(second_slice)) = (str.get(..i), str.get((i+1)..))
+                else { return Err(RuntimeError::out_of_range(i as i128, str.len()).into()); };
+            Ok(Value::String(String::from(first_slice) + &v + second_slice))
+        },
+        Value::ExprQueue     => {
+            let parsed_val = parse(e, span, v)?;
+            match (e.sets_expr(i, parsed_val)) {
+                Ok(_)  => Ok(Value::ExprQueue),
+                Err(_) => Err(RuntimeError::out_of_range(i as i128, e.len_exprs()).into())
+            }
+        },
+        Value::NamedQueue(name) => {
+            let len        = named(e, &name)?.len();
+            let parsed_val = parse(e, span, v)?;
+            for (k, expr,) in parsed_val.into_iter().enumerate() {
+                e.set_named(&name, i + k, expr).map_err(|_| RuntimeError::out_of_range((i + k) as i128, len))?;
+            }
+            Ok(Value::NamedQueue(name))
+        },
+        Value::Array   (mut arr) => {
+            let len = arr.len();
+            let Some(arr_at_i) = arr.get_mut(i)
+                else { return Err(RuntimeError::out_of_range(i as i128, len).into()); };
+            *arr_at_i = v;
+            Ok(Value::Array(arr))
+        },
+        Value::Error   (err) => Err(err),
+        q                    => Err(RuntimeError::type_mismatch("string, array or queue", q).into())
+    }
+}
+
+
+pub(crate) fn len(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [q] = take(args);
+    match (q) {
+        Value::String(v)     => Ok(Value::Int( v.len() as i128 )),
+        Value::Array(v)      => Ok(Value::Int( v.len() as i128 )),
+        Value::ExprQueue     => Ok(Value::Int( e.len_exprs() as i128 )),
+        Value::NamedQueue(n) => Ok(Value::Int( named(e, &n)?.len() as i128 )),
+        Value::Error(err)    => Err(err),
+        q                    => Err(RuntimeError::type_mismatch("string, array or queue", q).into())
+    }
+}
+
+
+fn file_name(fname : Value) -> Result<String, Box<RuntimeError>> {
+    match (fname) {
+        Value::Error(err) => Err(err),
+        fname             => Ok(fname.to_string())
+    }
+}
+
+fn io_error(action : &str, fname : String, err : io::Error) -> Box<RuntimeError> {
+    RuntimeError::new(ErrorKind::Io, format!("cannot {} {}: {}", action, fname, err))
+        .with_operands([Value::String(fname)]).into()
+}
+
+fn writable(e : &Executor, fname : &str) -> Result<(), Box<RuntimeError>> {
+    match (e.file_access()) {
+        Access::ReadWrite => Ok(()),
+        Access::ReadOnly  => Err(io_error("write", fname.to_string(), io::Error::new(io::ErrorKind::PermissionDenied, "files are read-only")))
+    }
+}
+
+pub(crate) fn fsread(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [fname] = take(args);
+    let fname = file_name(fname)?;
+    e.file_system().read_to_string(&fname).map(Value::String)
+        .map_err(|err| io_error("read", fname, err))
+}
+
+pub(crate) fn fswrite(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [fname, content] = take(args);
+    let fname   = file_name(fname)?;
+    let content = match (content) {
+        Value::String(content) => content,
+        Value::Error(err)      => { return Err(err); },
+        content                => { return Err(RuntimeError::type_mismatch("string", content).into()); }
+    };
+    writable(e, &fname)?;
+    e.file_system().write(&fname, content.as_bytes()).map(|_| Value::Unit)
+        .map_err(|err| io_error("write", fname, err))
+}
+
+pub(crate) fn fbread(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [fname] = take(args);
+    let fname = file_name(fname)?;
+    e.file_system().read(&fname).map(|bytes| Value::Array(bytes.into_iter().map(|b| Value::Int(b as i128)).collect()))
+        .map_err(|err| io_error("read", fname, err))
+}
+
+pub(crate) fn fbwrite(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [fname, content] = take(args);
+    let fname = file_name(fname)?;
+    let bytes = array(content)?.into_iter()
+        .map(|b| match (b) {
+            Value::Int(b)     => u8::try_from(b).map_err(|_| RuntimeError::new(ErrorKind::OutOfRange, format!("{} is not a byte", b))
+                .with_operands([Value::Int(b)]).into()),
+            Value::Error(err) => Err(err),
+            b                 => Err(RuntimeError::type_mismatch("int byte", b).into())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    writable(e, &fname)?;
+    e.file_system().write(&fname, &bytes).map(|_| Value::Unit)
+        .map_err(|err| io_error("write", fname, err))
+}
+
+
+pub(crate) fn readln(e : &mut Executor, _span : &Span, _args : Vec<Value>) -> ExecResult {
+    match (e.input().read_line()) {
+        Ok(Some(line)) => Ok(Value::String(line)),
+        Ok(None)       => Err(RuntimeError::new(ErrorKind::EndOfInput, "there is nothing left to read").into()),
+        Err(err)       => Err(RuntimeError::new(ErrorKind::Io, format!("cannot read the input: {}", err)).into())
+    }
+}
+
+pub(crate) fn readall(e : &mut Executor, _span : &Span, _args : Vec<Value>) -> ExecResult {
+    e.input().read_all().map(Value::String)
+        .map_err(|err| RuntimeError::new(ErrorKind::Io, format!("cannot read the input: {}", err)).into())
+}
+
+
+pub(crate) fn range(_e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [i0, i1] = take(args);
+    let i0 = match (i0) {
+        Value::Int(i0)    => i0,
+        Value::Error(err) => { return Err(err); },
+        i0                => { return Err(RuntimeError::type_mismatch("int", i0).into()); }
+    };
+    let i1 = match (i1) {
+        Value::Int(i1)    => i1,
+        Value::Error(err) => { return Err(err); },
+        i1                => { return Err(RuntimeError::type_mismatch("int", i1).into()); }
+    };
+    Ok(Value::Array((i0..i1).map(Value::Int).collect()))
+}
+
+
+pub(crate) fn str(_e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [v] = take(args);
+    Ok(Value::String(v.to_string()))
+}
+
+pub(crate) fn int(_e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [v] = take(args);
+    if let Value::Error(err) = v { return Err(err); }
+    match (v.to_string().parse::<i128>()) {
+        Ok(i)  => Ok(Value::Int(i)),
+        Err(_) => Err(RuntimeError::new(ErrorKind::Conversion, format!("cannot convert {:?} to int", v.to_string()))
+            .with_operands([v]).into())
+    }
+}
+
+
+fn unknown_queue(name : &str) -> Box<RuntimeError> {
+    RuntimeError::new(ErrorKind::Queue, format!("there is no queue named {:?}", name))
+        .with_operands([Value::String(name.to_string())]).into()
+}
+
+fn named<'l>(e : &'l Executor, name : &str) -> Result<&'l Rope<Expr>, Box<RuntimeError>> {
+    e.named_queue(name).ok_or_else(|| unknown_queue(name))
+}
+
+// The main queue is None.
+fn queue_ref(q : &Value) -> Result<Option<&str>, Box<RuntimeError>> {
+    match (q) {
+        Value::ExprQueue        => Ok(None),
+        Value::NamedQueue(name) => Ok(Some(name)),
+        Value::Error(err)       => Err(err.clone()),
+        q                       => Err(RuntimeError::type_mismatch("queue", q.clone()).into())
+    }
+}
+
+fn queue_name(name : Value) -> Result<String, Box<RuntimeError>> {
+    match (name) {
+        Value::String(name) if (is_queue_name(&name)) => Ok(name),
+        Value::String(name)                          => Err(RuntimeError::new(ErrorKind::Queue, format!("{:?} can't name a queue, as it is empty or has whitespace", name))
+            .with_operands([Value::String(name)]).into()),
+        Value::Error(err)                            => Err(err),
+        name                                         => Err(RuntimeError::type_mismatch("string", name).into())
+    }
+}
+
+pub(crate) fn mkqueue(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [name] = take(args);
+    let name = queue_name(name)?;
+    if (! e.create_queue(&name)) {
+        return Err(RuntimeError::new(ErrorKind::Queue, format!("there is already a queue named {:?}", name))
+            .with_operands([Value::String(name)]).into());
+    }
+    Ok(Value::NamedQueue(name))
+}
+
+pub(crate) fn named_queue(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [name] = take(args);
+    let name = queue_name(name)?;
+    named(e, &name)?;
+    Ok(Value::NamedQueue(name))
+}
+
+pub(crate) fn transfer(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [from, to, count] = take(args);
+    let count     = index(count)?;
+    let from_name = queue_ref(&from)?;
+    let to_name   = queue_ref(&to)?;
+    let available = match (from_name) {
+        None       => e.len_exprs() - 1,
+        Some(name) => named(e, name)?.len()
+    };
+    if let Some(name) = to_name { named(e, name)?; }
+    if (count > available) { return Err(RuntimeError::out_of_range(count as i128, available).into()); }
+    e.transfer(from_name, to_name, count).expect("the queues and count are checked");
+    Ok(to)
+}
+
+pub(crate) fn schedule(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [q, scheduled] = take(args);
+    let name = match (q) {
+        Value::NamedQueue(name) => name,
+        Value::Error(err)       => { return Err(err); },
+        q                       => { return Err(RuntimeError::type_mismatch("named queue", q).into()); }
+    };
+    let scheduled = match (scheduled) {
+        Value::Bool(scheduled) => scheduled,
+        Value::Error(err)      => { return Err(err); },
+        v                      => { return Err(RuntimeError::type_mismatch("bool", v).into()); }
+    };
+    e.schedule(&name, scheduled).map_err(|_| unknown_queue(&name))?;
+    Ok(Value::NamedQueue(name))
+}
+
+
+fn channel_name(c : Value) -> Result<String, Box<RuntimeError>> {
+    match (c) {
+        Value::Channel(name) => Ok(name),
+        Value::Error(err)    => Err(err),
+        c                    => Err(RuntimeError::type_mismatch("channel", c).into())
+    }
+}
+
+pub(crate) fn channel(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [name] = take(args);
+    let name = match (name) {
+        Value::String(name) => name,
+        Value::Error(err)   => { return Err(err); },
+        name                => { return Err(RuntimeError::type_mismatch("string", name).into()); }
+    };
+    e.open_channel(&name);
+    Ok(Value::Channel(name))
+}
+
+pub(crate) fn send(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [c, v] = take(args);
+    let name = channel_name(c)?;
+    if let Value::Error(err) = v { return Err(err); }
+    e.send(&name, v);
+    Ok(Value::Channel(name))
+}
+
+pub(crate) fn recv(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [c] = take(args);
+    let name = channel_name(c)?;
+    // When parked, the value is thrown away along with the rest of the try.
+    Ok(e.recv(&name).unwrap_or(Value::Unit))
+}
+
+pub(crate) fn spawn(e : &mut Executor, span : &Span, args : Vec<Value>) -> ExecResult {
+    let [v] = take(args);
+    let exprs = match (v) {
+        Value::Array(v)    => v.into_iter()
+            .map(|v| parse(e, span, v))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter().flatten().collect(),
+        v@Value::String(_) => parse(e, span, v)?,
+        Value::Error(err)  => { return Err(err); },
+        v                  => { return Err(RuntimeError::type_mismatch("string or array", v).into()); }
+    };
+    e.spawn(exprs);
+    Ok(Value::Unit)
+}
+
+
+pub(crate) fn reason(_e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [v] = take(args);
+    match (v) {
+        Value::Error(err) => Ok(Value::Array(vec![
+            Value::String(err.kind.name().to_string()),
+            Value::String(err.message)
+        ])),
+        _                 => Ok(Value::Unit)
+    }
+}
+
+
+pub(crate) fn exit(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let [code] = take(args);
+    let code = match (code) {
+        Value::Int(code)  => u8::try_from(code).map_err(|_| RuntimeError::new(ErrorKind::OutOfRange, format!("exit code {} is not between 0 and 255", code))
+            .with_operands([Value::Int(code)]))?,
+        Value::Error(err) => { return Err(err); },
+        code              => { return Err(RuntimeError::type_mismatch("int", code).into()); }
+    };
+    e.halt(code as i32);
+    Ok(Value::Unit)
+}
+
+
+pub(crate) fn call(e : &mut Executor, _span : &Span, args : Vec<Value>) -> ExecResult {
+    let mut args = args.into_iter();
+    let name = match (args.next().expect("arity is checked before dispatch")) {
+        Value::String(name) => name,
+        Value::Error(err)   => { return Err(err); },
+        name                => { return Err(RuntimeError::type_mismatch("string", name).into()); }
+    };
+    let args   = args.collect::<Vec<_>>();
+    let native = e.native(&name).cloned().ok_or_else(|| RuntimeError::new(ErrorKind::UnknownFunction, format!("there is no function named {:?}", name))
+        .with_operands([Value::String(name.clone())]))?;
+    if (args.len() != native.arity) {
+        return Err(RuntimeError::new(ErrorKind::Arity, format!("{} takes {} arguments, but was given {}", name, native.arity, args.len()))
+            .with_operands(args).into());
+    }
+    // Whatever the host hands back can change from call to call, just like input.
+    if let Some(cycles) = &mut e.cycles { cycles.read(); }
+    Ok((native.function)(e, args))
+}
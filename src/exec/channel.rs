@@ -0,0 +1,83 @@
+//! Channels, which pass values between an executor and the executors it
+//!  spawns. Channels are found by name, and every executor spawned by a
+//!  [`Scheduler`](crate::exec::Scheduler) shares its parent's channels.
+//!
+//! Receiving from an empty channel parks the executor: the rest of the
+//!  expression is skipped, the expression goes back to the front of its queue,
+//!  and the executor doesn't run again until the channel has a value. A tick
+//!  that parks isn't counted, and the next try gets the same tick number.
+//!
+//! The next try picks up at the `recv` itself. Every argument that had
+//!  finished on the way there keeps the value it had and isn't evaluated
+//!  again, so anything the expression did before `recv` only happens once.
+
+
+use crate::expr::Expr;
+use crate::exec::{ Executor, Value };
+use std::collections::{ BTreeMap, VecDeque };
+use std::sync::{ Arc, Mutex };
+
+
+/// Every channel of a family of executors, by name.
+pub(super) type Channels = Arc<Mutex<BTreeMap<String, VecDeque<Value>>>>;
+
+
+impl Executor {
+
+    /// Whether the script is waiting to receive from an empty channel.
+    pub fn blocked(&self) -> bool {
+        self.parked.as_ref().is_some_and(|name| self.channels.lock().unwrap().get(name).is_none_or(VecDeque::is_empty))
+    }
+
+    /// The number of values waiting in a channel.
+    pub fn channel_len(&self, name : &str) -> usize {
+        self.channels.lock().unwrap().get(name).map_or(0, VecDeque::len)
+    }
+
+    /// Takes the instructions given to `spawn` since this was last called.
+    pub fn take_spawned(&mut self) -> Vec<Vec<Expr>> {
+        std::mem::take(&mut self.spawned)
+    }
+
+    pub(super) fn open_channel(&mut self, name : &str) {
+        self.channels.lock().unwrap().entry(name.to_string()).or_default();
+    }
+
+    // Channels are outside of what the cycle detector hashes, so using them
+    //  counts as reading from outside the executor.
+    pub(super) fn send(&mut self, name : &str, value : Value) {
+        if let Some(cycles) = &mut self.cycles { cycles.read(); }
+        self.channels.lock().unwrap().entry(name.to_string()).or_default().push_back(value);
+    }
+
+    /// Takes the front of a channel, or parks the executor until it has one.
+    pub(super) fn recv(&mut self, name : &str) -> Option<Value> {
+        if let Some(cycles) = &mut self.cycles { cycles.read(); }
+        let value = self.channels.lock().unwrap().get_mut(name).and_then(VecDeque::pop_front);
+        if (value.is_none()) {
+            self.parked = Some(name.to_string());
+            self.unwind(vec![Value::Channel(name.to_string())]);
+        }
+        value
+    }
+
+    /// Remembers what a parked expression had finished. Called on the way out
+    ///  of each expression between the `recv` and the top of the tick,
+    ///  innermost first, with the values of the arguments it had finished.
+    pub(super) fn unwind(&mut self, finished : Vec<Value>) {
+        self.replay.extend(finished.into_iter().rev().map(Some));
+        self.replay.push(None);
+    }
+
+    /// What the next expression evaluated to last time, if it finished before
+    ///  the tick was parked.
+    pub(super) fn replay(&mut self) -> Option<Value> {
+        if (self.parked.is_some()) { return None; }
+        self.replay.pop().flatten()
+    }
+
+    pub(super) fn spawn(&mut self, exprs : Vec<Expr>) {
+        self.spawned.push(exprs);
+    }
+
+}
@@ -0,0 +1,158 @@
+//! Noticing when a run comes back to a state it has been in before. What an
+//!  expression does only depends on the queue and the expression executed
+//!  last, so once a state repeats, the script will loop forever.
+//!
+//! The state is fingerprinted by a hash of the queue, kept in a [`Rope`]
+//!  alongside it, so each change to the queue costs hashing the expression
+//!  plus time logarithmic in the length of the queue. Brent's algorithm finds
+//!  repeats while only keeping one earlier fingerprint. Reading input or
+//!  files, taking lines from a queue source, or calling a native function can
+//!  change what happens next without changing the queue, so states from
+//!  before a read are never compared with states after it.
+
+
+use crate::exec::{ Rope, Summary };
+use crate::expr::{ Expr, ExprKind, Lit };
+use core::hash::{ Hash, Hasher };
+use std::collections::hash_map::DefaultHasher;
+
+
+/// The state after tick `first` came back after tick `repeat`, and would
+///  keep coming back every `length` ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub first  : u64,
+    pub repeat : u64,
+    pub length : u64
+}
+
+
+// Hashes are polynomials in two bases, modulo a Mersenne prime, with each
+//  expression's coefficient being the hash of that expression.
+const P     : u64      = (1 << 61) - 1;
+const BASES : [u64; 2] = [0x0ba5_e5ba_11c0_ffee, 0x1d2c_3b4a_5968_7f01];
+
+fn mul(a : u64, b : u64) -> u64 {
+    ((a as u128 * b as u128) % (P as u128)) as u64
+}
+
+fn hash_expr(expr : &Expr) -> u64 {
+    fn feed(expr : &Expr, hasher : &mut DefaultHasher) {
+        match (&expr.kind) {
+            ExprKind::Builtin (builtin, args) => {
+                builtin.hash(hasher);
+                for arg in args { feed(arg, hasher); }
+            },
+            ExprKind::Lit     (lit)           => match (lit) {
+                Lit::Bool      (v) => { 0u8.hash(hasher); v.hash(hasher); },
+                Lit::Int       (v) => { 1u8.hash(hasher); v.hash(hasher); },
+                Lit::Float     (v) => { 2u8.hash(hasher); v.into_inner().hash(hasher); },
+                Lit::String    (v) => { 3u8.hash(hasher); v.hash(hasher); },
+                Lit::ExprQueue     => { 4u8.hash(hasher); }
+            }
+        }
+    }
+    let mut hasher = DefaultHasher::new();
+    feed(expr, &mut hasher);
+    hasher.finish() % P
+}
+
+
+/// The hashes `h` of a run of `n` expressions, as the polynomial sum of
+///  `h[i] * B^i` for each base `B`, along with `B^n` to shift the polynomials
+///  of whatever comes after the run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Polynomials {
+    sums  : [u64; 2],
+    shift : [u64; 2]
+}
+
+impl Default for Polynomials {
+    fn default() -> Self {
+        Self { sums : [0; 2], shift : [1; 2] }
+    }
+}
+
+impl Summary<u64> for Polynomials {
+    fn join(left : &Self, hash : &u64, right : &Self) -> Self {
+        let mut joined = Self::default();
+        for (k, base,) in BASES.into_iter().enumerate() {
+            let after = mul(left.shift[k], base);
+            joined.sums[k]  = (left.sums[k] + mul(*hash, left.shift[k]) + mul(right.sums[k], after)) % P;
+            joined.shift[k] = mul(after, right.shift[k]);
+        }
+        joined
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fingerprint {
+    sums   : [u64; 2],
+    len    : usize,
+    latest : u64,
+    reads  : u64
+}
+
+/// A hash of the queue, updated as it changes, and the fingerprint being
+///  compared against.
+pub(super) struct CycleDetector {
+    /// The hash of each queued expression.
+    hashes : Rope<u64, Polynomials>,
+    /// How many times the script has read from outside the executor.
+    reads  : u64,
+    /// The fingerprint being compared against, and the tick it is from.
+    saved  : Option<(Fingerprint, u64,)>,
+    /// How many ticks to compare against `saved` before replacing it.
+    power  : u64
+}
+
+impl CycleDetector {
+
+    pub(super) fn new<'l>(queued : impl IntoIterator<Item = &'l Expr>) -> Self {
+        Self {
+            hashes : queued.into_iter().map(hash_expr).collect(),
+            reads  : 0,
+            saved  : None,
+            power  : 1
+        }
+    }
+
+    pub(super) fn insert(&mut self, index : usize, expr : &Expr) {
+        self.hashes.insert(index, hash_expr(expr));
+    }
+
+    pub(super) fn set(&mut self, index : usize, expr : &Expr) {
+        self.hashes.set(index, hash_expr(expr));
+    }
+
+    pub(super) fn pop_front(&mut self) {
+        self.hashes.pop_front();
+    }
+
+    pub(super) fn read(&mut self) {
+        self.reads += 1;
+    }
+
+    /// Checks the state after `tick`, where `latest` was executed last.
+    pub(super) fn check(&mut self, latest : &Expr, tick : u64) -> Option<Cycle> {
+        let fingerprint = Fingerprint {
+            sums   : self.hashes.summary().sums,
+            len    : self.hashes.len(),
+            latest : hash_expr(latest),
+            reads  : self.reads
+        };
+        match (self.saved) {
+            None                  => { self.saved = Some((fingerprint, tick,)); },
+            Some((saved, first,)) => {
+                if (saved == fingerprint) { return Some(Cycle { first, repeat : tick, length : tick - first }); }
+                if (tick - first >= self.power) {
+                    self.saved  = Some((fingerprint, tick,));
+                    self.power *= 2;
+                }
+            }
+        }
+        None
+    }
+
+}
@@ -0,0 +1,114 @@
+use crate::expr::Expr;
+use crate::exec::Value;
+use core::fmt;
+
+
+/// Why an expression evaluated to [`Value::Error`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuntimeError {
+    pub kind     : ErrorKind,
+    pub message  : String,
+    /// The innermost expression that failed, once it is known.
+    pub expr     : Option<Expr>,
+    /// The values that the failing expression was given.
+    pub operands : Vec<Value>
+}
+
+impl RuntimeError {
+    pub fn new(kind : ErrorKind, message : impl Into<String>) -> Self {
+        Self { kind, message : message.into(), expr : None, operands : Vec::new() }
+    }
+
+    pub fn with_operands<I>(mut self, operands : I) -> Self
+    where
+        I : IntoIterator<Item = Value>
+    {
+        self.operands.extend(operands);
+        self
+    }
+
+    pub fn type_mismatch(expected : &str, found : Value) -> Self {
+        Self::new(ErrorKind::TypeMismatch, format!("expected {}, found {}", expected, found.type_name()))
+            .with_operands([found])
+    }
+
+    pub fn out_of_range(index : i128, len : usize) -> Self {
+        Self::new(ErrorKind::OutOfRange, format!("index {} is out of range for length {}", index, len))
+            .with_operands([Value::Int(index)])
+    }
+}
+
+impl From<RuntimeError> for Value {
+    fn from(error : RuntimeError) -> Self {
+        Value::Error(Box::new(error))
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    TypeMismatch,
+    OutOfRange,
+    DivisionByZero,
+    Conversion,
+    Syntax,
+    Io,
+    Arity,
+    EndOfInput,
+    Queue,
+    UnknownFunction,
+    Overflow,
+    Disabled
+}
+
+impl ErrorKind {
+    pub fn name(&self) -> &'static str {
+        match (self) {
+            Self::TypeMismatch    => "type_mismatch",
+            Self::OutOfRange      => "out_of_range",
+            Self::DivisionByZero  => "division_by_zero",
+            Self::Conversion      => "conversion",
+            Self::Syntax          => "syntax",
+            Self::Io              => "io",
+            Self::Arity           => "arity",
+            Self::EndOfInput      => "end_of_input",
+            Self::Queue           => "queue",
+            Self::UnknownFunction => "unknown_function",
+            Self::Overflow        => "overflow",
+            Self::Disabled        => "disabled"
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+
+/// Why an executor refused to change one of its queues.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueError {
+    /// The index or count reaches past the end of the queue.
+    OutOfRange,
+    /// No named queue has the given name.
+    UnknownQueue
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self) {
+            Self::OutOfRange   => write!(f, "out of range"),
+            Self::UnknownQueue => write!(f, "unknown queue")
+        }
+    }
+}
+
+impl std::error::Error for QueueError {}
@@ -0,0 +1,102 @@
+use crate::expr::{ Expr, ExprKind };
+use crate::exec::{ Executor, Value, RuntimeError, ErrorKind };
+use crate::parser;
+use crate::span::Origin;
+use std::io::{ self, BufRead, BufReader };
+
+
+/// Where `readln` and `readall` read from, and where an executor can take new
+///  expressions from while it runs.
+pub trait Input : Send {
+    /// The next line, without its line ending, or `None` once the input ends.
+    fn read_line(&mut self) -> io::Result<Option<String>>;
+
+    /// Everything left in the input.
+    fn read_all(&mut self) -> io::Result<String>;
+}
+
+
+/// Input from anything buffered, such as stdin or an in-memory cursor.
+pub struct Reader<R> {
+    reader : R
+}
+
+impl<R : BufRead + Send> Reader<R> {
+    pub fn new(reader : R) -> Self {
+        Self { reader }
+    }
+}
+
+impl Reader<BufReader<io::Stdin>> {
+    pub fn stdin() -> Self {
+        Self::new(BufReader::new(io::stdin()))
+    }
+}
+
+impl<R : BufRead + Send> Input for Reader<R> {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if (self.reader.read_line(&mut line)? == 0) { return Ok(None); }
+        if (line.ends_with('\n')) { line.pop(); }
+        if (line.ends_with('\r')) { line.pop(); }
+        Ok(Some(line))
+    }
+
+    fn read_all(&mut self) -> io::Result<String> {
+        let mut all = String::new();
+        self.reader.read_to_string(&mut all)?;
+        Ok(all)
+    }
+}
+
+
+/// A source of new expressions, one per line, for the end of the queue.
+pub(super) struct QueueSource {
+    input : Box<dyn Input>,
+    lines : usize
+}
+
+impl QueueSource {
+    pub(super) fn new(input : impl Input + 'static) -> Self {
+        Self { input : Box::new(input), lines : 0 }
+    }
+}
+
+impl Executor {
+
+    /// Reads lines from the queue source until something has been queued,
+    ///  or the source ends. Only reads one line if the queue isn't empty,
+    ///  so the source is consumed as the script runs.
+    pub(super) fn pull_queue_source(&mut self) -> Result<(), Box<RuntimeError>> {
+        while let Some(source) = &mut self.queue_source {
+            let line = match (source.input.read_line()) {
+                Ok(Some(line)) => line,
+                Ok(None)       => { self.queue_source = None; break; },
+                Err(err)       => {
+                    self.queue_source = None;
+                    return Err(RuntimeError::new(ErrorKind::Io, format!("cannot read the queue source: {}", err)).into());
+                }
+            };
+            source.lines += 1;
+            let number = source.lines;
+            if let Some(cycles) = &mut self.cycles { cycles.read(); }
+            let mut exprs = parser::parse_with_origin(&line, Origin::file("<stdin>"))
+                .map_err(|_| RuntimeError::new(ErrorKind::Syntax, format!("cannot parse line {} of <stdin>", number))
+                    .with_operands([Value::String(line)]))?;
+            for expr in &mut exprs { relocate(expr, number); }
+            let was_empty = self.queued_exprs.is_empty();
+            self.push_exprs(exprs);
+            if (! was_empty || ! self.queued_exprs.is_empty()) { break; }
+        }
+        Ok(())
+    }
+
+}
+
+// Each line is parsed on its own, so its spans start on line 1.
+fn relocate(expr : &mut Expr, line : usize) {
+    expr.span.line += line - 1;
+    if let ExprKind::Builtin(_, args) = &mut expr.kind {
+        for arg in args { relocate(arg, line); }
+    }
+}
@@ -1,5 +1,7 @@
-use crate::expr::{ Expr, Lit };
-use std::collections::VecDeque;
+use crate::builtin::Group;
+use crate::expr::{ Expr, ExprKind, Lit };
+use std::collections::{ BTreeMap, BTreeSet };
+use std::io::{ self, Write };
 
 
 mod expr;
@@ -8,33 +10,349 @@ pub use expr::*;
 mod value;
 pub use value::*;
 
+mod error;
+pub use error::*;
+
+mod run;
+pub use run::*;
+
+mod output;
+pub use output::*;
+
+mod input;
+pub use input::*;
+
+mod snapshot;
+pub use snapshot::*;
+
+mod trace;
+pub use trace::*;
+
+mod profile;
+pub use profile::*;
+
+mod cycle;
+pub use cycle::*;
+
+mod queues;
+pub use queues::*;
+
+mod channel;
+use channel::Channels;
+
+mod scheduler;
+pub use scheduler::*;
+
+mod observe;
+pub use observe::*;
+
+mod native;
+pub use native::*;
+
+mod builder;
+pub use builder::*;
+
+mod rope;
+pub use rope::*;
+
+pub mod vfs;
+use vfs::{ FileSystem, Access };
+
+pub(crate) mod builtins;
+
 
 pub struct Executor {
     latest_expr  : Expr,
-    queued_exprs : VecDeque<Expr>
+    queued_exprs : Rope<Expr>,
+    named_queues : Vec<NamedQueue>,
+    /// Where `next_turn` starts looking for a queue to execute.
+    turn         : usize,
+    ticks        : u64,
+    halted       : Option<i32>,
+    output       : Box<dyn Write + Send>,
+    file_system  : Box<dyn FileSystem>,
+    file_access  : Access,
+    input        : Box<dyn Input>,
+    queue_source : Option<QueueSource>,
+    tracer       : Option<Tracer>,
+    profiler     : Option<Profiler>,
+    cycles       : Option<CycleDetector>,
+    channels     : Channels,
+    /// The channel that `recv` is waiting on.
+    parked       : Option<String>,
+    /// What the parked expression had finished, next first, or `None` for
+    ///  each expression that was on the way to the `recv`.
+    replay       : Vec<Option<Value>>,
+    spawned      : Vec<Vec<Expr>>,
+    observers    : Vec<Box<dyn Observer>>,
+    natives      : BTreeMap<String, Native>,
+    overflow     : Overflow,
+    disabled     : BTreeSet<Group>,
+    /// What `run_source` runs with.
+    limits       : Limits,
+    /// Whether an expression is being run by `evaluate`.
+    evaluating   : bool
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Executor {
     pub fn new() -> Self {
         Self {
-            latest_expr  : Expr::Lit(Lit::Bool(false)),
-            queued_exprs : VecDeque::new()
+            latest_expr  : Expr::from(ExprKind::Lit(Lit::Bool(false))),
+            queued_exprs : Rope::new(),
+            named_queues : Vec::new(),
+            turn         : 0,
+            ticks        : 0,
+            halted       : None,
+            output       : Box::new(io::stdout()),
+            file_system  : Box::new(vfs::Disk),
+            file_access  : Access::ReadWrite,
+            input        : Box::new(Reader::stdin()),
+            queue_source : None,
+            tracer       : None,
+            profiler     : None,
+            cycles       : None,
+            channels     : Channels::default(),
+            parked       : None,
+            replay       : Vec::new(),
+            spawned      : Vec::new(),
+            observers    : Vec::new(),
+            natives      : BTreeMap::new(),
+            overflow     : Overflow::Error,
+            disabled     : BTreeSet::new(),
+            limits       : Limits::unlimited(),
+            evaluating   : false
         }
     }
+
+    /// Sends everything the script prints to `output`, instead of stdout.
+    pub fn with_output(mut self, output : impl Write + Send + 'static) -> Self {
+        self.set_output(output);
+        self
+    }
+
+    /// Sends every file the script reads or writes through `file_system`,
+    ///  instead of straight to disk.
+    pub fn with_file_system(mut self, file_system : impl FileSystem + 'static) -> Self {
+        self.set_file_system(file_system);
+        self
+    }
+
+    pub fn with_file_access(mut self, access : Access) -> Self {
+        self.file_access = access;
+        self
+    }
+
+    /// Has `readln` and `readall` read from `input`, instead of stdin.
+    pub fn with_input(mut self, input : impl Input + 'static) -> Self {
+        self.set_input(input);
+        self
+    }
+
+    /// Parses each line of `source` and adds it to the end of the queue, one
+    ///  line per tick. The script only completes once the source ends.
+    pub fn with_queue_source(mut self, source : impl Input + 'static) -> Self {
+        self.set_queue_source(source);
+        self
+    }
 }
 
 impl Executor {
     pub fn tick(&mut self) -> bool {
-        let Some(expr) = self.queued_exprs.pop_front()
-            else { return false; };
+        self.step().is_some()
+    }
+
+    /// Executes the next expression in the queue, returning what it evaluated
+    ///  to, or `None` if the queue was empty, the script has halted or it is
+    ///  waiting on an empty channel.
+    pub fn step(&mut self) -> Option<Value> {
+        if (self.halted.is_some()) { return None; }
+        if let Err(err) = self.pull_queue_source() { return Some(Value::Error(err)); }
+        self.step_queued()
+    }
+
+    // Like step, without reading from the queue source first.
+    fn step_queued(&mut self) -> Option<Value> {
+        if (self.halted.is_some() || self.blocked()) { return None; }
+        self.parked = None;
+        let turn = self.next_turn()?;
+        // Only the expression that parked can pick up where it left off, and
+        //  its queue is always next, unless it was taken off the schedule.
+        if (turn != self.turn) { self.replay.clear(); }
+        let expr = match (turn) {
+            0    => {
+                if let Some(cycles) = &mut self.cycles { cycles.pop_front(); }
+                self.queued_exprs.pop_front()
+            },
+            turn => {
+                if let Some(cycles) = &mut self.cycles { cycles.read(); }
+                self.named_queues[turn - 1].exprs.pop_front()
+            }
+        }.unwrap();
+        self.turn = turn + 1;
+        self.ticks += 1;
         self.latest_expr = expr.clone();
-        let _ = expr.execute(self);
-        true
+        let tick = self.ticks;
+        let named = (turn > 0).then(|| self.named_queues[turn - 1].name.clone());
+        self.trace(|| match (&named) {
+            None       => TraceEvent::Tick(tick, expr.clone()),
+            Some(name) => TraceEvent::In(name.clone(), Box::new(TraceEvent::Tick(tick, expr.clone())))
+        });
+        self.observe(|o| o.before_tick(tick, &expr));
+        let value = self.profile_tick(&expr, |e| expr.execute(e));
+        if (self.parked.is_some()) {
+            // Tried again with the same tick number once the channel has a
+            //  value, before any other queue gets a turn.
+            self.ticks -= 1;
+            self.turn   = turn;
+            match (&named) {
+                None       => {
+                    self.trace(|| TraceEvent::Requeue(expr.clone()));
+                    self.observe(|o| o.on_insert(1, &expr));
+                    if let Some(cycles) = &mut self.cycles { cycles.insert(0, &expr); }
+                    self.queued_exprs.push_front(expr.clone());
+                },
+                Some(name) => {
+                    self.trace(|| TraceEvent::In(name.clone(), Box::new(TraceEvent::Requeue(expr.clone()))));
+                    self.named_queues[turn - 1].exprs.push_front(expr.clone());
+                }
+            }
+        }
+        self.observe(|o| o.after_tick(tick, &expr, &value));
+        self.trace_result(&value);
+        Some(value)
+    }
+
+    /// Executes `expr` without changing the queues, the tick counter or whether
+    ///  the script has halted. Anything else it does, like printing or writing
+    ///  files, still happens, but isn't traced or observed. Builtins that use
+    ///  channels, spawn scripts or read input evaluate to a `disabled` error,
+    ///  since what they do couldn't be undone.
+    pub fn evaluate(&mut self, expr : &Expr) -> Value {
+        let latest     = self.latest_expr.clone();
+        let queued     = self.queued_exprs.clone();
+        let named      = self.named_queues.clone();
+        let turn       = self.turn;
+        let ticks      = self.ticks;
+        let halted     = self.halted;
+        let tracer     = self.tracer.take();
+        let profiler   = self.profiler.take();
+        let cycles     = self.cycles.take();
+        let parked     = self.parked.take();
+        let replay     = std::mem::take(&mut self.replay);
+        let observers  = self.take_observers();
+        let evaluating = std::mem::replace(&mut self.evaluating, true);
+        let value      = expr.execute(self);
+        self.latest_expr  = latest;
+        self.queued_exprs = queued;
+        self.named_queues = named;
+        self.turn         = turn;
+        self.ticks        = ticks;
+        self.halted       = halted;
+        self.tracer       = tracer;
+        self.profiler     = profiler;
+        self.cycles       = cycles;
+        self.parked       = parked;
+        self.replay       = replay;
+        self.observers    = observers;
+        self.evaluating   = evaluating;
+        value
     }
 }
 
 impl Executor {
 
+    /// The number of expressions executed so far.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// The exit code given to `exit`, once it has been called.
+    pub fn halted(&self) -> Option<i32> {
+        self.halted
+    }
+
+    pub fn halt(&mut self, code : i32) {
+        self.halted = Some(code);
+    }
+
+    /// Where the script prints to.
+    pub fn output(&mut self) -> &mut (dyn Write + Send) {
+        &mut *self.output
+    }
+
+    pub fn set_output(&mut self, output : impl Write + Send + 'static) {
+        self.output = Box::new(output);
+    }
+
+    /// Where the script reads and writes files.
+    pub fn file_system(&mut self) -> &mut dyn FileSystem {
+        if let Some(cycles) = &mut self.cycles { cycles.read(); }
+        &mut *self.file_system
+    }
+
+    pub fn set_file_system(&mut self, file_system : impl FileSystem + 'static) {
+        self.file_system = Box::new(file_system);
+    }
+
+    pub fn file_access(&self) -> Access {
+        self.file_access
+    }
+
+    pub fn set_file_access(&mut self, access : Access) {
+        self.file_access = access;
+    }
+
+    /// Where `readln` and `readall` read from.
+    pub fn input(&mut self) -> &mut dyn Input {
+        if let Some(cycles) = &mut self.cycles { cycles.read(); }
+        &mut *self.input
+    }
+
+    pub fn set_input(&mut self, input : impl Input + 'static) {
+        self.input = Box::new(input);
+    }
+
+    pub fn set_queue_source(&mut self, source : impl Input + 'static) {
+        self.queue_source = Some(QueueSource::new(source));
+    }
+
+    /// What int arithmetic does when the result doesn't fit in an int.
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    pub fn set_overflow(&mut self, overflow : Overflow) {
+        self.overflow = overflow;
+    }
+
+    /// Whether scripts may use the builtins in `group`. Every group is enabled
+    ///  at first.
+    pub fn group_enabled(&self, group : Group) -> bool {
+        ! self.disabled.contains(&group)
+    }
+
+    /// Has every builtin in `group` evaluate to a `disabled` error, or lets
+    ///  scripts use them again.
+    pub fn set_group_enabled(&mut self, group : Group, enabled : bool) {
+        if (enabled) { self.disabled.remove(&group); }
+        else { self.disabled.insert(group); }
+    }
+
+    /// The limits [`Executor::run_source`] runs with.
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn set_limits(&mut self, limits : Limits) {
+        self.limits = limits;
+    }
+
     pub fn get_expr(&self, index : usize) -> Option<&Expr> {
         match (index) {
             0   => Some(&self.latest_expr),
@@ -43,10 +361,15 @@ impl Executor {
     }
 
     pub fn get_exprs(&self, i0 : usize, i1 : usize) -> Option<Vec<&Expr>> {
-        (i0..i1).map(|index| self.get_expr(index)).collect()
+        if (i0 >= i1) { return Some(Vec::new()); }
+        if (i1 > self.len_exprs()) { return None; }
+        let latest = (i0 == 0 && i1 > 0).then_some(&self.latest_expr);
+        Some(latest.into_iter().chain(self.queued_exprs.range(i0.max(1) - 1..i1.max(1) - 1)).collect())
     }
+    /// The expressions from `i0` up to `i1`, printed, since that is how `gets`
+    ///  hands them to scripts.
     pub fn get_exprs_values(&self, i0 : usize, i1 : usize) -> Option<Vec<Value>> {
-        (i0..i1).map(|index| self.get_expr(index).map(|expr| Value::String(expr.to_string()))).collect()
+        Some(self.get_exprs(i0, i1)?.into_iter().map(|expr| Value::String(expr.to_string())).collect())
     }
 
     pub fn len_exprs(&self) -> usize {
@@ -57,21 +380,29 @@ impl Executor {
     where
         I : IntoIterator<Item = Expr>
     {
-        self.queued_exprs.extend(exprs);
+        for expr in exprs {
+            self.trace(|| TraceEvent::Push(expr.clone()));
+            self.observe(|o| o.on_push(&expr));
+            if let Some(cycles) = &mut self.cycles { cycles.insert(self.queued_exprs.len(), &expr); }
+            self.queued_exprs.push_back(expr);
+        }
     }
 
-    pub fn set_expr(&mut self, index : usize, expr : Expr) -> Result<(), ()> {
+    pub fn set_expr(&mut self, index : usize, expr : Expr) -> Result<(), QueueError> {
         match (index) {
             0   => Ok(()),
             1.. => {
-                let ptr = self.queued_exprs.get_mut(index - 1).ok_or(())?;
-                *ptr = expr;
+                if (index > self.queued_exprs.len()) { return Err(QueueError::OutOfRange); }
+                self.trace(|| TraceEvent::Set(index, expr.clone()));
+                self.observe(|o| o.on_set(index, &expr));
+                if let Some(cycles) = &mut self.cycles { cycles.set(index - 1, &expr); }
+                self.queued_exprs[index - 1] = expr;
                 Ok(())
             }
         }
     }
 
-    pub fn sets_expr<I>(&mut self, start_index : usize, exprs : I) -> Result<(), ()>
+    pub fn sets_expr<I>(&mut self, start_index : usize, exprs : I) -> Result<(), QueueError>
     where
         I : IntoIterator<Item = Expr>
     {
@@ -82,18 +413,25 @@ impl Executor {
         Ok(())
     }
 
-    pub fn insert_expr(&mut self, index : usize, expr : Expr) -> Result<(), ()> { 
+    pub fn insert_expr(&mut self, index : usize, expr : Expr) -> Result<(), QueueError> { 
         match (index) {
             0 => {
                 // don't use expr
                 // push everything forward
-                self.queued_exprs.push_front(self.latest_expr.clone());
+                let latest = self.latest_expr.clone();
+                self.trace(|| TraceEvent::Insert(1, latest.clone()));
+                self.observe(|o| o.on_insert(1, &latest));
+                if let Some(cycles) = &mut self.cycles { cycles.insert(0, &latest); }
+                self.queued_exprs.push_front(latest);
                 Ok(())
             },
             1.. => {
                 if (index > self.queued_exprs.len()) {
-                    return Err(());
+                    return Err(QueueError::OutOfRange);
                 }
+                self.trace(|| TraceEvent::Insert(index, expr.clone()));
+                self.observe(|o| o.on_insert(index, &expr));
+                if let Some(cycles) = &mut self.cycles { cycles.insert(index - 1, &expr); }
                 self.queued_exprs.insert(index - 1, expr);
                 Ok(())
             }
@@ -101,7 +439,7 @@ impl Executor {
         }
     }
     
-    pub fn inserts_expr<I>(&mut self, start_index : usize, exprs : I) -> Result<(), ()>
+    pub fn inserts_expr<I>(&mut self, start_index : usize, exprs : I) -> Result<(), QueueError>
     where
         I : IntoIterator<Item = Expr>
     {
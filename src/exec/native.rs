@@ -0,0 +1,52 @@
+//! Functions the program embedding the interpreter registers, for scripts to
+//!  call with `call`.
+
+
+use crate::exec::{ Executor, Value };
+use std::sync::Arc;
+
+
+pub type NativeFn = dyn Fn(&mut Executor, Vec<Value>) -> Value + Send + Sync;
+
+/// A function registered with [`Executor::register`].
+#[derive(Clone)]
+pub struct Native {
+    /// The number of arguments it takes, not counting its name.
+    pub arity    : usize,
+    pub function : Arc<NativeFn>
+}
+
+
+impl Executor {
+
+    /// Lets scripts call `function` as `call "name" args...`, with exactly
+    ///  `arity` arguments. Replaces any function already registered under
+    ///  `name`.
+    ///
+    /// The function gets the arguments already evaluated, errors included, and
+    ///  can return an error value to fail the expression.
+    pub fn register<F>(&mut self, name : impl Into<String>, arity : usize, function : F)
+    where
+        F : Fn(&mut Executor, Vec<Value>) -> Value + Send + Sync + 'static
+    {
+        self.natives.insert(name.into(), Native { arity, function : Arc::new(function) });
+    }
+
+    pub fn with_native<F>(mut self, name : impl Into<String>, arity : usize, function : F) -> Self
+    where
+        F : Fn(&mut Executor, Vec<Value>) -> Value + Send + Sync + 'static
+    {
+        self.register(name, arity, function);
+        self
+    }
+
+    pub fn native(&self, name : &str) -> Option<&Native> {
+        self.natives.get(name)
+    }
+
+    /// The names of every registered function, in order.
+    pub fn native_names(&self) -> impl Iterator<Item = &str> {
+        self.natives.keys().map(String::as_str)
+    }
+
+}
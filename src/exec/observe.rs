@@ -0,0 +1,60 @@
+//! Hooks for tools that watch a script run, like coverage or visualizers,
+//!  without changing how it runs.
+//!
+//! Observers only see the main queue and the ticks. Executors without any
+//!  observers skip the hooks entirely, building nothing for them.
+
+
+use crate::expr::Expr;
+use crate::exec::{ Executor, Value };
+
+
+/// Watches what an [`Executor`] does. Every method does nothing unless
+///  implemented, so an observer only implements what it needs.
+///
+/// Indices are the same as for [`Executor::get_expr`], where `0` is the
+///  expression executed last.
+#[allow(unused_variables)]
+pub trait Observer : Send {
+    /// Called before tick number `tick` executes `expr`.
+    fn before_tick(&mut self, tick : u64, expr : &Expr) { }
+    /// Called after tick number `tick` executed `expr`, with what it evaluated
+    ///  to.
+    fn after_tick(&mut self, tick : u64, expr : &Expr, value : &Value) { }
+    /// Called when `expr` is added to the end of the queue.
+    fn on_push(&mut self, expr : &Expr) { }
+    /// Called when the expression at `index` is overwritten with `expr`.
+    fn on_set(&mut self, index : usize, expr : &Expr) { }
+    /// Called when `expr` is inserted at `index`. Inserting at index 0 copies
+    ///  the expression executed last to the front of the queue, so it is seen
+    ///  as that expression inserted at 1, the same as in a trace.
+    fn on_insert(&mut self, index : usize, expr : &Expr) { }
+    /// Called when the script prints `value`.
+    fn on_print(&mut self, value : &Value) { }
+}
+
+
+impl Executor {
+
+    /// Has `observer` see everything the executor does from now on, after
+    ///  every observer added before it.
+    pub fn add_observer(&mut self, observer : impl Observer + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    pub fn with_observer(mut self, observer : impl Observer + 'static) -> Self {
+        self.add_observer(observer);
+        self
+    }
+
+    /// Removes every observer, returning them in the order they were added.
+    pub fn take_observers(&mut self) -> Vec<Box<dyn Observer>> {
+        std::mem::take(&mut self.observers)
+    }
+
+    /// Calls `hook` with every observer, in the order they were added.
+    pub(super) fn observe(&mut self, mut hook : impl FnMut(&mut dyn Observer)) {
+        for observer in &mut self.observers { hook(&mut **observer); }
+    }
+
+}
@@ -0,0 +1,44 @@
+use std::io::{ self, Write };
+use std::sync::{ Arc, Mutex };
+
+
+/// An output that keeps everything written to it, for reading back later.
+///  Clones share the same buffer, so one can be given to an
+///  [`Executor`](crate::exec::Executor) and another kept to read from.
+#[derive(Debug, Clone, Default)]
+pub struct Capture {
+    buffer : Arc<Mutex<Vec<u8>>>
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything written so far.
+    pub fn bytes(&self) -> Vec<u8> {
+        self.buffer.lock().unwrap().clone()
+    }
+
+    /// Everything written so far, with invalid UTF-8 replaced.
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.lock().unwrap()).into_owned()
+    }
+
+    /// Everything written so far, leaving the buffer empty.
+    pub fn take(&self) -> String {
+        let bytes = std::mem::take(&mut *self.buffer.lock().unwrap());
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf : &[u8]) -> io::Result<usize> {
+        self.buffer.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+//! Counting which expressions and source lines a script spends its time in.
+
+
+use crate::expr::{ Expr, ExprKind };
+use crate::exec::Executor;
+use crate::span::{ Origin, Span };
+use core::fmt;
+use std::collections::HashMap;
+use std::io::{ self, Write };
+use std::time::{ Duration, Instant };
+
+
+/// The name parsing pushed strings is counted under.
+pub const PARSE : &str = "(parse)";
+
+
+/// How often something was evaluated, and how long it took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub count : u64,
+    /// Including everything evaluated inside it.
+    pub total : Duration,
+    /// Not including anything evaluated inside it.
+    pub own   : Duration
+}
+
+/// The ticks whose expression was written on one source line, including
+///  expressions pushed by that line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineStats {
+    pub ticks : u64,
+    pub time  : Duration,
+    /// The part of `time` spent parsing pushed strings.
+    pub parse : Duration
+}
+
+
+/// Where the time went while profiling. Printing it gives a table of the
+///  slowest expressions and lines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    pub ticks  : u64,
+    /// The time spent executing ticks.
+    pub time   : Duration,
+    /// By builtin keyword, or `literal`, and [`PARSE`] for parsing pushed
+    ///  strings.
+    pub kinds  : HashMap<&'static str, Stats>,
+    /// By the line the tick's expression was written on, like `script.push:3`.
+    pub lines  : HashMap<String, LineStats>,
+    /// Time not spent inside anything else, by the line of the tick and the
+    ///  kinds of the expressions it was nested in.
+    pub stacks : HashMap<String, Duration>
+}
+
+impl Profile {
+
+    /// Writes `stacks` in the collapsed format that flamegraph tools read,
+    ///  one `line;kind;kind nanoseconds` per line.
+    pub fn write_collapsed(&self, mut writer : impl Write) -> io::Result<()> {
+        let mut stacks = self.stacks.iter().collect::<Vec<_>>();
+        stacks.sort();
+        for (stack, time,) in stacks {
+            writeln!(writer, "{} {}", stack, time.as_nanos())?;
+        }
+        writer.flush()
+    }
+
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ticks in {:.3?}", self.ticks, self.time)?;
+
+        let mut kinds = self.kinds.iter().collect::<Vec<_>>();
+        kinds.sort_by(|(a, a_stats,), (b, b_stats,)| b_stats.own.cmp(&a_stats.own).then(a.cmp(b)));
+        writeln!(f)?;
+        writeln!(f, "{:<12} {:>12} {:>12} {:>12}", "expression", "count", "total", "own")?;
+        for (kind, stats,) in kinds {
+            writeln!(f, "{:<12} {:>12} {:>12.3?} {:>12.3?}", kind, stats.count, stats.total, stats.own)?;
+        }
+
+        let mut lines = self.lines.iter().collect::<Vec<_>>();
+        lines.sort_by(|(a, a_stats,), (b, b_stats,)| b_stats.time.cmp(&a_stats.time).then(a.cmp(b)));
+        let width = lines.iter().map(|(line, _,)| line.len()).max().unwrap_or(0).max(4);
+        writeln!(f)?;
+        writeln!(f, "{:<width$} {:>12} {:>12} {:>12}", "line", "ticks", "time", "parsing")?;
+        for (line, stats,) in lines {
+            writeln!(f, "{:<width$} {:>12} {:>12.3?} {:>12.3?}", line, stats.ticks, stats.time, stats.parse)?;
+        }
+        Ok(())
+    }
+}
+
+
+struct Frame {
+    kind     : &'static str,
+    start    : Instant,
+    children : Duration
+}
+
+/// The profile being recorded, and the expressions being evaluated right now.
+#[derive(Default)]
+pub(super) struct Profiler {
+    profile : Profile,
+    line    : String,
+    frames  : Vec<Frame>
+}
+
+impl Profiler {
+
+    fn enter(&mut self, kind : &'static str) {
+        self.frames.push(Frame { kind, start : Instant::now(), children : Duration::ZERO });
+    }
+
+    fn exit(&mut self) {
+        let key   = self.stack_key();
+        let frame = self.frames.pop().unwrap();
+        let total = frame.start.elapsed();
+        let own   = total.saturating_sub(frame.children);
+        if let Some(parent) = self.frames.last_mut() { parent.children += total; }
+
+        let stats = self.profile.kinds.entry(frame.kind).or_default();
+        stats.count += 1;
+        stats.total += total;
+        stats.own   += own;
+        *self.profile.stacks.entry(key).or_default() += own;
+    }
+
+    fn stack_key(&self) -> String {
+        let mut key = self.line.clone();
+        for frame in &self.frames {
+            key.push(';');
+            key.push_str(frame.kind);
+        }
+        key
+    }
+
+}
+
+fn kind_name(expr : &Expr) -> &'static str {
+    match (&expr.kind) {
+        ExprKind::Builtin (builtin, _) => builtin.keyword(),
+        ExprKind::Lit     (_)          => "literal"
+    }
+}
+
+fn line_name(span : &Span) -> String {
+    let span = span.root();
+    match (&span.origin) {
+        Origin::File(name) => format!("{}:{}", name, span.line),
+        _                  => format!("line {}", span.line)
+    }
+}
+
+
+impl Executor {
+
+    /// Starts recording a new [`Profile`], replacing any profile being
+    ///  recorded already.
+    pub fn start_profile(&mut self) {
+        self.profiler = Some(Profiler::default());
+    }
+
+    /// The profile recorded so far, if profiling.
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profiler.as_ref().map(|profiler| &profiler.profile)
+    }
+
+    pub fn stop_profile(&mut self) -> Option<Profile> {
+        self.profiler.take().map(|profiler| profiler.profile)
+    }
+
+    /// Runs `f` as the whole of a tick executing `expr`.
+    pub(super) fn profile_tick<T>(&mut self, expr : &Expr, f : impl FnOnce(&mut Self) -> T) -> T {
+        let Some(profiler) = &mut self.profiler
+            else { return f(self); };
+        profiler.line = line_name(&expr.span);
+        let parsed = profiler.profile.kinds.get(PARSE).map_or(Duration::ZERO, |stats| stats.total);
+        let start  = Instant::now();
+        let value  = f(self);
+        let time   = start.elapsed();
+        if let Some(profiler) = &mut self.profiler {
+            let parsed  = profiler.profile.kinds.get(PARSE).map_or(Duration::ZERO, |stats| stats.total) - parsed;
+            let profile = &mut profiler.profile;
+            profile.ticks += 1;
+            profile.time  += time;
+            let line = profile.lines.entry(profiler.line.clone()).or_default();
+            line.ticks += 1;
+            line.time  += time;
+            line.parse += parsed;
+        }
+        value
+    }
+
+    /// Runs `f` as evaluating `expr`, or as parsing if `expr` is `None`.
+    pub(super) fn profile_expr<T>(&mut self, expr : Option<&Expr>, f : impl FnOnce(&mut Self) -> T) -> T {
+        let Some(profiler) = &mut self.profiler
+            else { return f(self); };
+        profiler.enter(expr.map_or(PARSE, kind_name));
+        let value = f(self);
+        if let Some(profiler) = &mut self.profiler { profiler.exit(); }
+        value
+    }
+
+}
@@ -0,0 +1,153 @@
+//! Queues besides the main one, which scripts create by name to keep data
+//!  apart from the expressions being executed.
+//!
+//! A named queue holds expressions just like the main queue, but it has no
+//!  expression executed last, so its index 0 is its front. It is only executed
+//!  once scheduled, and then the main queue and every scheduled queue that
+//!  isn't empty take turns, one tick each, in the order they were created.
+
+
+use crate::expr::Expr;
+use crate::exec::{ Executor, QueueError, Rope, TraceEvent };
+
+
+#[derive(Clone)]
+pub(super) struct NamedQueue {
+    pub(super) name      : String,
+    pub(super) exprs     : Rope<Expr>,
+    pub(super) scheduled : bool
+}
+
+/// Whether `name` can be given to a queue. Names are written unquoted in
+///  snapshots and traces, so they can't be empty or contain whitespace.
+pub fn is_queue_name(name : &str) -> bool {
+    ! name.is_empty() && ! name.contains(char::is_whitespace)
+}
+
+
+impl Executor {
+
+    pub(super) fn named_index(&self, name : &str) -> Option<usize> {
+        self.named_queues.iter().position(|queue| queue.name == name)
+    }
+
+    // Named queues aren't part of what the cycle detector hashes, so changing
+    //  them counts as reading from outside the executor.
+    fn named_mut(&mut self, name : &str) -> Option<&mut NamedQueue> {
+        if let Some(cycles) = &mut self.cycles { cycles.read(); }
+        self.named_queues.iter_mut().find(|queue| queue.name == name)
+    }
+
+    /// Creates an empty queue, returning `false` if the name is already taken
+    ///  or isn't a valid queue name.
+    pub fn create_queue(&mut self, name : &str) -> bool {
+        if (! is_queue_name(name) || self.named_index(name).is_some()) { return false; }
+        self.trace(|| TraceEvent::Create(name.to_string()));
+        self.named_queues.push(NamedQueue { name : name.to_string(), exprs : Rope::new(), scheduled : false });
+        true
+    }
+
+    pub fn named_queue(&self, name : &str) -> Option<&Rope<Expr>> {
+        self.named_queues.iter().find(|queue| queue.name == name).map(|queue| &queue.exprs)
+    }
+
+    /// The names of every named queue, in the order they were created.
+    pub fn queue_names(&self) -> impl Iterator<Item = &str> {
+        self.named_queues.iter().map(|queue| queue.name.as_str())
+    }
+
+    pub fn push_named<I>(&mut self, name : &str, exprs : I) -> Result<(), QueueError>
+    where
+        I : IntoIterator<Item = Expr>
+    {
+        if (self.named_index(name).is_none()) { return Err(QueueError::UnknownQueue); }
+        for expr in exprs {
+            self.trace(|| TraceEvent::In(name.to_string(), Box::new(TraceEvent::Push(expr.clone()))));
+            self.named_mut(name).unwrap().exprs.push_back(expr);
+        }
+        Ok(())
+    }
+
+    pub fn insert_named(&mut self, name : &str, index : usize, expr : Expr) -> Result<(), QueueError> {
+        let queue = self.named_queue(name).ok_or(QueueError::UnknownQueue)?;
+        if (index > queue.len()) { return Err(QueueError::OutOfRange); }
+        self.trace(|| TraceEvent::In(name.to_string(), Box::new(TraceEvent::Insert(index, expr.clone()))));
+        self.named_mut(name).unwrap().exprs.insert(index, expr);
+        Ok(())
+    }
+
+    pub fn set_named(&mut self, name : &str, index : usize, expr : Expr) -> Result<(), QueueError> {
+        let queue = self.named_queue(name).ok_or(QueueError::UnknownQueue)?;
+        if (index >= queue.len()) { return Err(QueueError::OutOfRange); }
+        self.trace(|| TraceEvent::In(name.to_string(), Box::new(TraceEvent::Set(index, expr.clone()))));
+        self.named_mut(name).unwrap().exprs[index] = expr;
+        Ok(())
+    }
+
+    /// Sets whether a named queue is executed alongside the main queue.
+    pub fn schedule(&mut self, name : &str, scheduled : bool) -> Result<(), QueueError> {
+        if (self.named_index(name).is_none()) { return Err(QueueError::UnknownQueue); }
+        self.trace(|| TraceEvent::Schedule(name.to_string(), scheduled));
+        self.named_mut(name).unwrap().scheduled = scheduled;
+        Ok(())
+    }
+
+    /// Moves `count` expressions from the front of one queue to the end of
+    ///  another, where `None` is the main queue. Only the main queue's queued
+    ///  expressions are moved, never the expression executed last. Nothing
+    ///  changes if either queue doesn't exist or `from` is too short.
+    pub fn transfer(&mut self, from : Option<&str>, to : Option<&str>, count : usize) -> Result<(), QueueError> {
+        let available = match (from) {
+            None       => self.queued_exprs.len(),
+            Some(name) => self.named_queue(name).ok_or(QueueError::UnknownQueue)?.len()
+        };
+        if (to.is_some_and(|name| self.named_index(name).is_none())) { return Err(QueueError::UnknownQueue); }
+        if (count > available) { return Err(QueueError::OutOfRange); }
+        for _ in 0..count {
+            let expr = match (from) {
+                None       => {
+                    self.trace(|| TraceEvent::Take);
+                    if let Some(cycles) = &mut self.cycles { cycles.pop_front(); }
+                    self.queued_exprs.pop_front()
+                },
+                Some(name) => {
+                    self.trace(|| TraceEvent::In(name.to_string(), Box::new(TraceEvent::Take)));
+                    self.named_mut(name).unwrap().exprs.pop_front()
+                }
+            }.unwrap();
+            match (to) {
+                None       => self.push_exprs([expr]),
+                Some(name) => self.push_named(name, [expr])?
+            }
+        }
+        Ok(())
+    }
+
+    /// Which queue the next tick takes its expression from: `0` for the main
+    ///  queue, or one more than the index of a named queue.
+    pub(super) fn next_turn(&self) -> Option<usize> {
+        let turns = self.named_queues.len() + 1;
+        (0..turns).map(|offset| (self.turn + offset) % turns).find(|&turn| match (turn) {
+            0    => ! self.queued_exprs.is_empty(),
+            turn => {
+                let queue = &self.named_queues[turn - 1];
+                queue.scheduled && ! queue.exprs.is_empty()
+            }
+        })
+    }
+
+    /// The expression the next tick will execute, if any.
+    pub fn next_expr(&self) -> Option<&Expr> {
+        match (self.next_turn()?) {
+            0    => self.queued_exprs.front(),
+            turn => self.named_queues[turn - 1].exprs.front()
+        }
+    }
+
+    /// The number of expressions waiting in every queue, including named
+    ///  queues that aren't scheduled.
+    pub fn queued_total(&self) -> usize {
+        self.queued_exprs.len() + self.named_queues.iter().map(|queue| queue.exprs.len()).sum::<usize>()
+    }
+
+}
@@ -0,0 +1,425 @@
+//! Where every queue keeps its expressions.
+//!
+//! A [`Rope`] is a sequence stored as a balanced binary tree, where each node
+//!  knows how many values are under it. Getting, setting, inserting or
+//!  removing at any index only walks from the root to that index, so it takes
+//!  logarithmic time wherever the index is, where a `VecDeque` has to move
+//!  every value after it.
+//!
+//! Nodes are shared between clones, and a node is only copied when a clone
+//!  that shares it changes it, so cloning a queue takes constant time.
+//!
+//! Each node can also keep a [`Summary`] of its subtree, like the hash of
+//!  every value in it, which is kept up to date in the same logarithmic time.
+
+
+use core::cmp::Ordering;
+use core::fmt;
+use core::mem;
+use core::ops::{ Index, IndexMut, Range };
+use std::sync::Arc;
+
+
+type Link<T, S> = Option<Arc<Node<T, S>>>;
+
+/// A sequence with logarithmic indexed edits and constant time clones, which
+///  keeps a summary `S` of its values.
+pub struct Rope<T, S = ()> {
+    root : Link<T, S>
+}
+
+/// What a [`Rope`] keeps about the values in each of its subtrees.
+pub trait Summary<T> : Clone + Default {
+    /// The summary of the values in `left`, then `value`, then the values in
+    ///  `right`. The default summary is that of no values at all.
+    fn join(left : &Self, value : &T, right : &Self) -> Self;
+}
+
+impl<T> Summary<T> for () {
+    fn join(_ : &Self, _ : &T, _ : &Self) -> Self { }
+}
+
+#[derive(Clone)]
+struct Node<T, S> {
+    value   : T,
+    left    : Link<T, S>,
+    right   : Link<T, S>,
+    /// The number of values in this subtree, this one included.
+    len     : usize,
+    height  : u8,
+    summary : S
+}
+
+
+impl<T, S> Rope<T, S> {
+
+    pub fn new() -> Self {
+        Self { root : None }
+    }
+
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn get(&self, mut index : usize) -> Option<&T> {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            let left = len(&node.left);
+            match (index.cmp(&left)) {
+                Ordering::Less    => { link = &node.left; },
+                Ordering::Equal   => { return Some(&node.value); },
+                Ordering::Greater => {
+                    index -= left + 1;
+                    link   = &node.right;
+                }
+            }
+        }
+        None
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, S> {
+        self.range(0..self.len())
+    }
+
+    /// The values at `range`, in order, without visiting any before it.
+    ///
+    /// # Panics
+    /// Panics if the range ends before it starts, or past the end of the rope.
+    pub fn range(&self, range : Range<usize>) -> Iter<'_, T, S> {
+        assert!(range.start <= range.end && range.end <= self.len(), "range {:?} is out of bounds for length {}", range, self.len());
+        let mut stack = Vec::new();
+        let mut link  = &self.root;
+        let mut index = range.start;
+        while let Some(node) = link {
+            let left = len(&node.left);
+            match (index.cmp(&left)) {
+                Ordering::Less    => {
+                    stack.push(&**node);
+                    link = &node.left;
+                },
+                Ordering::Equal   => {
+                    stack.push(&**node);
+                    break;
+                },
+                Ordering::Greater => {
+                    index -= left + 1;
+                    link   = &node.right;
+                }
+            }
+        }
+        Iter { stack, remaining : range.len() }
+    }
+
+}
+
+impl<T : Clone> Rope<T> {
+
+    // Only ropes without summaries hand out mutable values, since a summary
+    //  couldn't be updated once the value changes.
+    pub fn get_mut(&mut self, mut index : usize) -> Option<&mut T> {
+        if (index >= self.len()) { return None; }
+        let mut node = Arc::make_mut(self.root.as_mut()?);
+        loop {
+            let left = len(&node.left);
+            match (index.cmp(&left)) {
+                Ordering::Less    => { node = Arc::make_mut(node.left.as_mut()?); },
+                Ordering::Equal   => { return Some(&mut node.value); },
+                Ordering::Greater => {
+                    index -= left + 1;
+                    node   = Arc::make_mut(node.right.as_mut()?);
+                }
+            }
+        }
+    }
+
+}
+
+impl<T : Clone, S : Summary<T>> Rope<T, S> {
+
+    /// The summary of every value, in order.
+    pub fn summary(&self) -> S {
+        summary(&self.root)
+    }
+
+    /// Replaces the value at `index`, returning the one it replaced.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index : usize, value : T) -> T {
+        assert!(index < self.len(), "index {} is out of bounds for length {}", index, self.len());
+        set(self.root.as_mut().unwrap(), index, value)
+    }
+
+    /// Inserts `value` so that it ends up at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is past the end of the rope.
+    pub fn insert(&mut self, index : usize, value : T) {
+        assert!(index <= self.len(), "index {} is out of bounds for length {}", index, self.len());
+        self.root = Some(insert(self.root.take(), index, value));
+    }
+
+    pub fn remove(&mut self, index : usize) -> Option<T> {
+        if (index >= self.len()) { return None; }
+        let (root, value,) = remove(self.root.take()?, index);
+        self.root = root;
+        Some(value)
+    }
+
+    pub fn push_front(&mut self, value : T) {
+        self.insert(0, value);
+    }
+
+    pub fn push_back(&mut self, value : T) {
+        self.insert(self.len(), value);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.remove(0)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.remove(self.len().checked_sub(1)?)
+    }
+
+}
+
+
+fn len<T, S>(link : &Link<T, S>) -> usize {
+    link.as_ref().map_or(0, |node| node.len)
+}
+
+fn height<T, S>(link : &Link<T, S>) -> u8 {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+fn summary<T, S : Summary<T>>(link : &Link<T, S>) -> S {
+    link.as_ref().map_or_else(S::default, |node| node.summary.clone())
+}
+
+impl<T, S : Summary<T>> Node<T, S> {
+
+    fn new(value : T, left : Link<T, S>, right : Link<T, S>) -> Self {
+        let mut node = Self { value, left, right, len : 0, height : 0, summary : S::default() };
+        node.update();
+        node
+    }
+
+    fn update(&mut self) {
+        self.len     = len(&self.left) + len(&self.right) + 1;
+        self.height  = height(&self.left).max(height(&self.right)) + 1;
+        self.summary = S::join(&summary(&self.left), &self.value, &summary(&self.right));
+    }
+
+}
+
+fn rotate_left<T : Clone, S : Summary<T>>(mut node : Arc<Node<T, S>>) -> Arc<Node<T, S>> {
+    let parent    = Arc::make_mut(&mut node);
+    let mut right = parent.right.take().expect("a node rotated left has a right child");
+    let child     = Arc::make_mut(&mut right);
+    parent.right  = child.left.take();
+    parent.update();
+    child.left    = Some(node);
+    child.update();
+    right
+}
+
+fn rotate_right<T : Clone, S : Summary<T>>(mut node : Arc<Node<T, S>>) -> Arc<Node<T, S>> {
+    let parent   = Arc::make_mut(&mut node);
+    let mut left = parent.left.take().expect("a node rotated right has a left child");
+    let child    = Arc::make_mut(&mut left);
+    parent.left  = child.right.take();
+    parent.update();
+    child.right  = Some(node);
+    child.update();
+    left
+}
+
+/// Brings the heights of the children of `node` back within one of each
+///  other, after one of them changed by at most one.
+fn balance<T : Clone, S : Summary<T>>(mut node : Arc<Node<T, S>>) -> Arc<Node<T, S>> {
+    let parent = Arc::make_mut(&mut node);
+    parent.update();
+    let (left, right,) = (height(&parent.left), height(&parent.right),);
+    if (left > right + 1) {
+        let child   = parent.left.take().unwrap();
+        parent.left = Some(if (height(&child.left) < height(&child.right)) { rotate_left(child) } else { child });
+        rotate_right(node)
+    } else if (right > left + 1) {
+        let child    = parent.right.take().unwrap();
+        parent.right = Some(if (height(&child.right) < height(&child.left)) { rotate_right(child) } else { child });
+        rotate_left(node)
+    } else { node }
+}
+
+fn insert<T : Clone, S : Summary<T>>(link : Link<T, S>, index : usize, value : T) -> Arc<Node<T, S>> {
+    let Some(mut node) = link
+        else { return Arc::new(Node::new(value, None, None)); };
+    let parent = Arc::make_mut(&mut node);
+    let left   = len(&parent.left);
+    if (index <= left) { parent.left = Some(insert(parent.left.take(), index, value)); }
+    else { parent.right = Some(insert(parent.right.take(), index - left - 1, value)); }
+    balance(node)
+}
+
+fn remove<T : Clone, S : Summary<T>>(mut node : Arc<Node<T, S>>, index : usize) -> (Link<T, S>, T,) {
+    let parent = Arc::make_mut(&mut node);
+    let left   = len(&parent.left);
+    let value  = match (index.cmp(&left)) {
+        Ordering::Less    => {
+            let (child, value,) = remove(parent.left.take().unwrap(), index);
+            parent.left = child;
+            value
+        },
+        Ordering::Greater => {
+            let (child, value,) = remove(parent.right.take().unwrap(), index - left - 1);
+            parent.right = child;
+            value
+        },
+        // The value after this one takes its place, if there is one.
+        Ordering::Equal   => match (parent.right.take()) {
+            None        => {
+                let left = parent.left.take();
+                return (left, Arc::unwrap_or_clone(node).value,);
+            },
+            Some(right) => {
+                let (child, next,) = remove(right, 0);
+                parent.right = child;
+                mem::replace(&mut parent.value, next)
+            }
+        }
+    };
+    (Some(balance(node)), value,)
+}
+
+fn set<T : Clone, S : Summary<T>>(node : &mut Arc<Node<T, S>>, index : usize, value : T) -> T {
+    let node = Arc::make_mut(node);
+    let left = len(&node.left);
+    let old  = match (index.cmp(&left)) {
+        Ordering::Less    => set(node.left.as_mut().unwrap(), index, value),
+        Ordering::Equal   => mem::replace(&mut node.value, value),
+        Ordering::Greater => set(node.right.as_mut().unwrap(), index - left - 1, value)
+    };
+    node.update();
+    old
+}
+
+/// Builds a perfectly balanced tree out of the next `count` values.
+fn build<T, S : Summary<T>>(values : &mut impl Iterator<Item = T>, count : usize) -> Link<T, S> {
+    if (count == 0) { return None; }
+    let left  = build(values, count / 2);
+    let value = values.next()?;
+    let right = build(values, count - count / 2 - 1);
+    Some(Arc::new(Node::new(value, left, right)))
+}
+
+
+/// An iterator over the values of a [`Rope`], made by [`Rope::iter`] or
+///  [`Rope::range`].
+pub struct Iter<'l, T, S = ()> {
+    /// The nodes whose values are still to come, along with everything to
+    ///  their right, the next one last.
+    stack     : Vec<&'l Node<T, S>>,
+    remaining : usize
+}
+
+impl<'l, T, S> Iterator for Iter<'l, T, S> {
+    type Item = &'l T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        let node     = self.stack.pop()?;
+        let mut link = &node.right;
+        while let Some(next) = link {
+            self.stack.push(next);
+            link = &next.left;
+        }
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining),)
+    }
+}
+
+impl<T, S> ExactSizeIterator for Iter<'_, T, S> { }
+
+impl<'l, T, S> IntoIterator for &'l Rope<T, S> {
+    type Item     = &'l T;
+    type IntoIter = Iter<'l, T, S>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+
+impl<T, S> Clone for Rope<T, S> {
+    fn clone(&self) -> Self {
+        Self { root : self.root.clone() }
+    }
+}
+
+impl<T, S> Default for Rope<T, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, S : Summary<T>> FromIterator<T> for Rope<T, S> {
+    fn from_iter<I : IntoIterator<Item = T>>(values : I) -> Self {
+        let values = values.into_iter().collect::<Vec<_>>();
+        let count  = values.len();
+        Self { root : build(&mut values.into_iter(), count) }
+    }
+}
+
+impl<T : Clone, S : Summary<T>> Extend<T> for Rope<T, S> {
+    fn extend<I : IntoIterator<Item = T>>(&mut self, values : I) {
+        for value in values { self.push_back(value); }
+    }
+}
+
+impl<T, S> Index<usize> for Rope<T, S> {
+    type Output = T;
+    fn index(&self, index : usize) -> &T {
+        let len = self.len();
+        self.get(index).unwrap_or_else(|| panic!("index {} is out of bounds for length {}", index, len))
+    }
+}
+
+impl<T : Clone> IndexMut<usize> for Rope<T> {
+    fn index_mut(&mut self, index : usize) -> &mut T {
+        let len = self.len();
+        self.get_mut(index).unwrap_or_else(|| panic!("index {} is out of bounds for length {}", index, len))
+    }
+}
+
+impl<T : PartialEq, S> PartialEq for Rope<T, S> {
+    fn eq(&self, other : &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T : Eq, S> Eq for Rope<T, S> { }
+
+impl<T : fmt::Debug, S> fmt::Debug for Rope<T, S> {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,153 @@
+use crate::expr::Expr;
+use crate::parser::{ self, ParserError };
+use crate::exec::{ Executor, Value, RuntimeError, Cycle, CycleDetector };
+use std::time::{ Duration, Instant };
+
+
+/// When [`Executor::run`] should give up on a script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The most expressions to execute in this run.
+    pub ticks  : u64,
+    /// The longest this run may take. Only checked between expressions.
+    pub time   : Option<Duration>,
+    /// The most expressions that may be waiting in the queue, counting every
+    ///  named queue too.
+    pub queue  : Option<usize>,
+    /// Whether to stop once the script is certain to loop forever. What has
+    ///  been seen is kept for the next run, if that run detects cycles too.
+    pub cycles : bool,
+    /// Whether to stop once an expression in the queue evaluates to an error.
+    ///  Otherwise the error is thrown away and the script carries on, like
+    ///  with [`Executor::tick`].
+    pub errors : bool
+}
+
+impl Limits {
+    pub fn new(ticks : u64) -> Self {
+        Self { ticks, time : None, queue : None, cycles : false, errors : false }
+    }
+
+    /// No limits at all, like looping over [`Executor::tick`].
+    pub fn unlimited() -> Self {
+        Self::new(u64::MAX)
+    }
+
+    pub fn with_time(mut self, time : Duration) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    pub fn with_queue(mut self, queue : usize) -> Self {
+        self.queue = Some(queue);
+        self
+    }
+
+    /// Stops the run once the queue and the expression executed last are the
+    ///  same as they were after an earlier tick of the run.
+    pub fn with_cycle_detection(mut self) -> Self {
+        self.cycles = true;
+        self
+    }
+
+    pub fn with_stop_on_error(mut self) -> Self {
+        self.errors = true;
+        self
+    }
+}
+
+
+/// Why [`Executor::run`] returned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// The queue and every scheduled queue are empty.
+    Completed,
+    /// [`Limits::ticks`] expressions were executed, and more are queued.
+    BudgetExhausted,
+    /// More than [`Limits::queue`] expressions were queued.
+    QueueLimitExceeded,
+    /// The run took longer than [`Limits::time`].
+    TimeLimitExceeded,
+    /// The script came back to an earlier state, so it would never end.
+    CycleDetected(Cycle),
+    /// The script is waiting to receive from an empty channel.
+    Blocked,
+    /// The script called `exit` with the given code.
+    Halted(i32),
+    /// An expression in the queue evaluated to an error, and
+    ///  [`Limits::errors`] was set. Also when the queue source failed.
+    Failed(Box<RuntimeError>)
+}
+
+/// What happened during a call to [`Executor::run`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunReport {
+    pub outcome    : Outcome,
+    /// The number of expressions executed during this run.
+    pub ticks      : u64,
+    /// The most expressions that were waiting in the queues at once.
+    pub peak_queue : usize,
+    pub elapsed    : Duration
+}
+
+
+impl Executor {
+
+    /// Executes expressions until the queue is empty, the script halts, or
+    ///  one of the limits is reached.
+    ///
+    /// The executor can be run again afterwards, to continue with new limits.
+    pub fn run(&mut self, limits : Limits) -> RunReport {
+        self.run_tracing(limits, |_, _| { })
+    }
+
+    /// Parses `source`, adds it to the end of the queue and runs it with the
+    ///  executor's own [`limits`](Executor::limits). Nothing is queued if any
+    ///  of it fails to parse.
+    pub fn run_source(&mut self, source : &str) -> Result<RunReport, ParserError> {
+        self.push_exprs(parser::parse(source)?);
+        Ok(self.run(self.limits))
+    }
+
+    /// Like [`Executor::run`], but calls `trace` with the tick number and the
+    ///  expression before each expression is executed.
+    pub fn run_tracing<F>(&mut self, limits : Limits, mut trace : F) -> RunReport
+    where
+        F : FnMut(u64, &Expr)
+    {
+        let start     = Instant::now();
+        let mut ticks = 0;
+        let mut peak  = self.queued_total();
+        if (! limits.cycles) {
+            self.cycles = None;
+        } else if (self.cycles.is_none()) {
+            let mut cycles = CycleDetector::new(&self.queued_exprs);
+            cycles.check(&self.latest_expr, self.ticks);
+            self.cycles = Some(cycles);
+        }
+        let outcome   = loop {
+            if let Some(code) = self.halted { break Outcome::Halted(code); }
+            if (self.blocked()) { break Outcome::Blocked; }
+            if let Err(err) = self.pull_queue_source() { break Outcome::Failed(err); }
+            let queued = self.queued_total();
+            peak = peak.max(queued);
+            if (limits.queue.is_some_and(|limit| queued > limit)) { break Outcome::QueueLimitExceeded; }
+            let Some(next) = self.next_expr()
+                else { break Outcome::Completed; };
+            if (ticks >= limits.ticks) { break Outcome::BudgetExhausted; }
+            if (limits.time.is_some_and(|limit| start.elapsed() >= limit)) { break Outcome::TimeLimitExceeded; }
+
+            trace(self.ticks + 1, next);
+            let value = self.step_queued();
+            // A tick that parks is tried again, and only counts once.
+            if (self.parked.is_none()) { ticks += 1; }
+            // Halting wins over whatever the halting expression evaluated to.
+            if let Some(Value::Error(err)) = value && limits.errors && self.halted.is_none() { break Outcome::Failed(err); }
+            if let Some(cycles) = &mut self.cycles && let Some(cycle) = cycles.check(&self.latest_expr, self.ticks) {
+                break Outcome::CycleDetected(cycle);
+            }
+        };
+        RunReport { outcome, ticks, peak_queue : peak, elapsed : start.elapsed() }
+    }
+
+}
@@ -0,0 +1,131 @@
+//! Runs a script along with every script it spawns.
+//!
+//! Executors take turns, one tick each, in the order they were spawned,
+//!  skipping the ones that have finished or are waiting on an empty channel.
+//!  Nothing depends on timing, so the same scripts given the same input always
+//!  run in the same order.
+
+
+use crate::expr::Expr;
+use crate::exec::{ Executor, Limits, Outcome };
+use std::time::{ Duration, Instant };
+
+
+/// A family of executors: the main one, and every one spawned since.
+pub struct Scheduler {
+    /// Executors by id, the main executor first. Finished executors are kept,
+    ///  so that ids stay the same.
+    executors : Vec<Executor>,
+    /// Where the next turn starts looking for an executor to run.
+    cursor    : usize,
+    spawner   : Box<dyn FnMut() -> Executor>
+}
+
+/// What happened during a call to [`Scheduler::run`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleReport {
+    pub outcome  : Outcome,
+    /// The id of the executor the outcome came from, or `0` for the main
+    ///  executor if it didn't come from any one of them.
+    pub executor : usize,
+    /// The number of expressions executed during this run, by every executor.
+    pub ticks    : u64,
+    pub elapsed  : Duration
+}
+
+
+impl Scheduler {
+
+    pub fn new(main : Executor) -> Self {
+        Self { executors : vec![main], cursor : 0, spawner : Box::new(Executor::new) }
+    }
+
+    /// Sets how spawned executors are made, before they are given their
+    ///  expressions and their parent's channels. They also get the native
+    ///  functions their parent has that they don't, and can't use any builtin
+    ///  group their parent can't.
+    pub fn with_spawner<F>(mut self, spawner : F) -> Self
+    where
+        F : FnMut() -> Executor + 'static
+    {
+        self.spawner = Box::new(spawner);
+        self
+    }
+
+    pub fn main(&self) -> &Executor {
+        &self.executors[0]
+    }
+
+    pub fn main_mut(&mut self) -> &mut Executor {
+        &mut self.executors[0]
+    }
+
+    /// Every executor, by id.
+    pub fn executors(&self) -> &[Executor] {
+        &self.executors
+    }
+
+    /// Like [`Executor::run`], for every executor at once. [`Limits::ticks`]
+    ///  and [`Limits::time`] are for the whole run, and the other limits are
+    ///  for each executor.
+    ///
+    /// The run ends when the main executor halts, or any executor fails while
+    ///  [`Limits::errors`] is set. A spawned executor that halts just stops. If every executor left is
+    ///  waiting on an empty channel, the run ends as [`Outcome::Blocked`].
+    pub fn run(&mut self, limits : Limits) -> ScheduleReport {
+        self.run_tracing(limits, |_, _, _| { })
+    }
+
+    /// Like [`Scheduler::run`], but calls `trace` with the executor's id, its
+    ///  tick number and the expression before each expression is executed.
+    pub fn run_tracing<F>(&mut self, limits : Limits, mut trace : F) -> ScheduleReport
+    where
+        F : FnMut(usize, u64, &Expr)
+    {
+        let start     = Instant::now();
+        let mut ticks = 0;
+        let turn      = Limits { ticks : 1, time : None, ..limits };
+        let (outcome, executor,) = loop {
+            let Some(id) = self.next_runnable()
+                else { break match (self.executors.iter().position(Executor::blocked)) {
+                    Some(id) => (Outcome::Blocked, id,),
+                    None     => (Outcome::Completed, 0,)
+                }; };
+            if (ticks >= limits.ticks) { break (Outcome::BudgetExhausted, 0,); }
+            if (limits.time.is_some_and(|limit| start.elapsed() >= limit)) { break (Outcome::TimeLimitExceeded, 0,); }
+
+            self.cursor = id + 1;
+            let report = self.executors[id].run_tracing(turn, |tick, expr| trace(id, tick, expr));
+            ticks += report.ticks;
+            self.spawn_children(id);
+            match (report.outcome) {
+                Outcome::Completed | Outcome::BudgetExhausted | Outcome::Blocked => { },
+                Outcome::Halted(_) if (id != 0)                                  => { },
+                outcome                                                          => { break (outcome, id,); }
+            }
+        };
+        ScheduleReport { outcome, executor, ticks, elapsed : start.elapsed() }
+    }
+
+    fn next_runnable(&self) -> Option<usize> {
+        let count = self.executors.len();
+        (0..count).map(|offset| (self.cursor + offset) % count).find(|&id| {
+            let e = &self.executors[id];
+            e.halted.is_none() && ! e.blocked() && (e.next_expr().is_some() || e.queue_source.is_some())
+        })
+    }
+
+    fn spawn_children(&mut self, parent : usize) {
+        for exprs in self.executors[parent].take_spawned() {
+            let mut child = (self.spawner)();
+            child.channels = self.executors[parent].channels.clone();
+            for (name, native,) in &self.executors[parent].natives {
+                child.natives.entry(name.clone()).or_insert_with(|| native.clone());
+            }
+            child.disabled.extend(self.executors[parent].disabled.iter().copied());
+            child.push_exprs(exprs);
+            self.executors.push(child);
+        }
+    }
+
+}
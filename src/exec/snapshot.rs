@@ -0,0 +1,170 @@
+//! Saving an executor's state to text and loading it back.
+//!
+//! A snapshot is a header line followed by one field per line:
+//!
+//! ```text
+//! sisyphus-snapshot 1
+//! ticks 42
+//! halted 3
+//! latest print "the expression executed last"
+//! queued push queue "and one line"
+//! queued print "for every queued expression"
+//! named data
+//! scheduled data
+//! in data print "for every expression in a named queue"
+//! turn 1
+//! ```
+//!
+//! `halted` only appears once the script has called `exit`, and `turn`, which
+//!  queue takes the next tick, once there are named queues. Expressions are
+//!  written in their printed form, so where they were written is not kept,
+//!  and neither is anything about the executor's output, input, queue source
+//!  or file system.
+
+
+use crate::expr::Expr;
+use crate::exec::{ Executor, NamedQueue, Rope, is_queue_name };
+use crate::parser;
+use core::fmt;
+use std::error::Error;
+use std::io::{ self, Read, Write };
+
+
+const HEADER : &str = "sisyphus-snapshot 1";
+
+
+/// Why a snapshot couldn't be loaded.
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// `line` is 1-indexed.
+    Format { line : usize, message : String }
+}
+
+impl SnapshotError {
+    fn format(line : usize, message : impl Into<String>) -> Self {
+        Self::Format { line, message : message.into() }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err : io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self) {
+            Self::Io     (err)             => write!(f, "{}", err),
+            Self::Format { line, message } => write!(f, "line {} of the snapshot: {}", line, message)
+        }
+    }
+}
+
+impl Error for SnapshotError { }
+
+
+impl Executor {
+
+    /// Writes the queues, the expression executed last, the tick counter and
+    ///  whether the script has halted.
+    ///
+    /// Channels and spawned scripts can't be written, so this fails without
+    ///  writing anything once the script has used a channel or spawned a
+    ///  script that hasn't been taken by a scheduler yet.
+    pub fn save(&self, mut writer : impl Write) -> io::Result<()> {
+        if (self.parked.is_some() || ! self.spawned.is_empty() || ! self.channels.lock().unwrap().is_empty()) {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "snapshots can't hold channels or spawned scripts"));
+        }
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(writer, "ticks {}", self.ticks)?;
+        if let Some(code) = self.halted { writeln!(writer, "halted {}", code)?; }
+        writeln!(writer, "latest {}", self.latest_expr)?;
+        for expr in &self.queued_exprs { writeln!(writer, "queued {}", expr)?; }
+        for queue in &self.named_queues {
+            writeln!(writer, "named {}", queue.name)?;
+            if (queue.scheduled) { writeln!(writer, "scheduled {}", queue.name)?; }
+            for expr in &queue.exprs { writeln!(writer, "in {} {}", queue.name, expr)?; }
+        }
+        if (! self.named_queues.is_empty()) { writeln!(writer, "turn {}", self.turn)?; }
+        writer.flush()
+    }
+
+    /// Replaces the state of this executor with a snapshot written by
+    ///  [`Executor::save`]. Nothing changes if the snapshot is invalid.
+    pub fn load(&mut self, mut reader : impl Read) -> Result<(), SnapshotError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let mut lines = text.lines().enumerate().map(|(i, line,)| (i + 1, line,));
+
+        match (lines.next()) {
+            Some((_, HEADER,)) => { },
+            _                  => { return Err(SnapshotError::format(1, format!("expected {:?}", HEADER))); }
+        }
+        let mut ticks  = None;
+        let mut halted = None;
+        let mut latest = None;
+        let mut queued = Rope::new();
+        let mut named  = Vec::<NamedQueue>::new();
+        let mut turn   = 0;
+        for (number, line,) in lines {
+            let (field, value,) = line.split_once(' ').unwrap_or((line, ""));
+            match (field) {
+                "ticks"     => { ticks  = Some(value.parse().map_err(|_| SnapshotError::format(number, "bad tick count"))?); },
+                "halted"    => { halted = Some(value.parse().map_err(|_| SnapshotError::format(number, "bad exit code"))?); },
+                "latest"    => { latest = Some(parse_expr(number, value)?); },
+                "queued"    => { queued.push_back(parse_expr(number, value)?); },
+                "named"     => {
+                    if (! is_queue_name(value) || named.iter().any(|queue| queue.name == value)) {
+                        return Err(SnapshotError::format(number, format!("bad queue name {:?}", value)));
+                    }
+                    named.push(NamedQueue { name : value.to_string(), exprs : Rope::new(), scheduled : false });
+                },
+                "scheduled" => { named_queue(&mut named, number, value)?.scheduled = true; },
+                "in"        => {
+                    let (name, expr,) = value.split_once(' ').ok_or_else(|| SnapshotError::format(number, "expected a queue and an expression"))?;
+                    let expr = parse_expr(number, expr)?;
+                    named_queue(&mut named, number, name)?.exprs.push_back(expr);
+                },
+                "turn"      => { turn = value.parse().map_err(|_| SnapshotError::format(number, "bad turn"))?; },
+                ""          => { },
+                field       => { return Err(SnapshotError::format(number, format!("unknown field {:?}", field))); }
+            }
+        }
+
+        let end    = text.lines().count();
+        let ticks  = ticks.ok_or_else(|| SnapshotError::format(end, "missing ticks"))?;
+        let latest = latest.ok_or_else(|| SnapshotError::format(end, "missing latest"))?;
+        self.ticks        = ticks;
+        self.latest_expr  = latest;
+        self.halted       = halted;
+        self.queued_exprs = queued;
+        self.named_queues = named;
+        self.turn         = turn;
+        self.cycles       = None;
+        self.parked       = None;
+        self.replay       = Vec::new();
+        self.trace_state();
+        Ok(())
+    }
+
+}
+
+fn named_queue<'l>(named : &'l mut [NamedQueue], line : usize, name : &str) -> Result<&'l mut NamedQueue, SnapshotError> {
+    named.iter_mut().find(|queue| queue.name == name)
+        .ok_or_else(|| SnapshotError::format(line, format!("no queue named {:?}", name)))
+}
+
+fn parse_expr(line : usize, text : &str) -> Result<Expr, SnapshotError> {
+    parse_one(text).map_err(|message| SnapshotError::format(line, message))
+}
+
+/// Parses the printed form of a single expression.
+pub(super) fn parse_one(text : &str) -> Result<Expr, String> {
+    let mut exprs = parser::parse(text).map_err(|err| err.to_string())?;
+    match (exprs.len()) {
+        1 => Ok(exprs.remove(0)),
+        n => Err(format!("expected one expression, found {}", n))
+    }
+}
@@ -0,0 +1,335 @@
+//! Recording how the queue changes, tick by tick, and replaying it.
+//!
+//! A trace is a header line followed by one event per line:
+//!
+//! ```text
+//! sisyphus-trace 1
+//! reset 0
+//! latest false
+//! push push queue "print 1"
+//! tick 1 push queue "print 1"
+//! push print 1
+//! result "exprqueue"
+//! tick 2 print 1
+//! result "1"
+//! ```
+//!
+//! `reset` and `latest` describe the whole state of the queue, when tracing
+//!  starts or a snapshot is loaded. After that, `tick` takes the expression at
+//!  the front of the queue and executes it, `take` removes it without
+//!  executing it, and `push`, `insert` and `set` change the queue with the
+//!  same indices as [`Executor::insert_expr`] and [`Executor::set_expr`].
+//!  `requeue` puts the expression of a tick that parked on an empty channel
+//!  back at the front, to be executed again as the same tick. `result` is the
+//!  printed form of what the last tick evaluated to.
+//!
+//! Named queues are made by `create <name>` and `schedule <name> <bool>`, and
+//!  `in <name>` before any other event applies it to that queue instead.
+
+
+use crate::expr::{ Expr, ExprKind, Lit };
+use crate::exec::{ Executor, Value };
+use crate::exec::snapshot::parse_one;
+use core::fmt;
+use std::error::Error;
+use std::io::{ self, BufRead, Write };
+
+
+const HEADER : &str = "sisyphus-trace 1";
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEvent {
+    /// The queue was emptied and the tick counter set.
+    Reset    { ticks : u64 },
+    /// The expression at index 0 of the queue was replaced.
+    Latest   (Expr),
+    Push     (Expr),
+    Insert   (usize, Expr),
+    Set      (usize, Expr),
+    /// The front of the queue was executed as the given tick.
+    Tick     (u64, Expr),
+    /// The last tick parked on an empty channel, so its expression went back
+    ///  to the front of the queue and the tick will happen again.
+    Requeue  (Expr),
+    /// The printed form of what the last tick evaluated to.
+    Result   (String),
+    /// The front of the queue was removed without being executed.
+    Take,
+    /// An empty named queue was created.
+    Create   (String),
+    Schedule (String, bool),
+    /// The event happened to the named queue, rather than the main one.
+    In       (String, Box<TraceEvent>)
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self) {
+            Self::Reset    { ticks }     => write!(f, "reset {}", ticks),
+            Self::Latest   (expr)        => write!(f, "latest {}", expr),
+            Self::Push     (expr)        => write!(f, "push {}", expr),
+            Self::Insert   (index, expr) => write!(f, "insert {} {}", index, expr),
+            Self::Set      (index, expr) => write!(f, "set {} {}", index, expr),
+            Self::Tick     (tick, expr)  => write!(f, "tick {} {}", tick, expr),
+            Self::Requeue  (expr)        => write!(f, "requeue {}", expr),
+            Self::Result   (value)       => write!(f, "result {}", Lit::String(value.clone())),
+            Self::Take                   => write!(f, "take"),
+            Self::Create   (name)        => write!(f, "create {}", name),
+            Self::Schedule (name, on)    => write!(f, "schedule {} {}", name, on),
+            Self::In       (name, event) => write!(f, "in {} {}", name, event)
+        }
+    }
+}
+
+impl TraceEvent {
+    /// The tick number and expression of a tick, in any queue.
+    fn tick(&self) -> Option<(u64, &Expr,)> {
+        match (self) {
+            Self::Tick (tick, expr) => Some((*tick, expr,)),
+            Self::In   (_, event)   => event.tick(),
+            _                       => None
+        }
+    }
+
+    fn parse(line : &str) -> Result<Self, String> {
+        if (line == "take") { return Ok(Self::Take); }
+        let (kind, rest,) = line.split_once(' ').ok_or("expected an event and its arguments")?;
+        let number = |text : &str| text.parse().map_err(|_| format!("bad number {:?}", text));
+        let indexed = |rest : &str| -> Result<(usize, Expr,), String> {
+            let (index, expr,) = rest.split_once(' ').ok_or("expected an index and an expression")?;
+            Ok((number(index)? as usize, parse_one(expr)?,))
+        };
+        match (kind) {
+            "reset"  => Ok(Self::Reset { ticks : number(rest)? }),
+            "latest" => Ok(Self::Latest(parse_one(rest)?)),
+            "push"   => Ok(Self::Push(parse_one(rest)?)),
+            "insert" => { let (index, expr,) = indexed(rest)?; Ok(Self::Insert(index, expr)) },
+            "set"    => { let (index, expr,) = indexed(rest)?; Ok(Self::Set(index, expr)) },
+            "tick"   => {
+                let (tick, expr,) = rest.split_once(' ').ok_or("expected a tick and an expression")?;
+                Ok(Self::Tick(number(tick)?, parse_one(expr)?))
+            },
+            "requeue"  => Ok(Self::Requeue(parse_one(rest)?)),
+            "result" => match (parse_one(rest)?.kind) {
+                ExprKind::Lit(Lit::String(value)) => Ok(Self::Result(value)),
+                _                                 => Err("expected a string".to_string())
+            },
+            "create"   => Ok(Self::Create(rest.to_string())),
+            "schedule" => {
+                let (name, on,) = rest.split_once(' ').ok_or("expected a queue and a bool")?;
+                Ok(Self::Schedule(name.to_string(), on.parse().map_err(|_| format!("bad bool {:?}", on))?))
+            },
+            "in"       => {
+                let (name, event,) = rest.split_once(' ').ok_or("expected a queue and an event")?;
+                match (Self::parse(event)?) {
+                    event@(Self::Push(_) | Self::Insert(..) | Self::Set(..) | Self::Tick(..) | Self::Requeue(_) | Self::Take) => {
+                        Ok(Self::In(name.to_string(), Box::new(event)))
+                    },
+                    event => Err(format!("{} can't happen in a named queue", event))
+                }
+            },
+            kind     => Err(format!("unknown event {:?}", kind))
+        }
+    }
+}
+
+
+/// Writes [`TraceEvent`]s for an executor, keeping the first write error.
+pub(super) struct Tracer {
+    writer : Box<dyn Write + Send>,
+    error  : Option<io::Error>
+}
+
+impl Tracer {
+    fn write(&mut self, event : &TraceEvent) {
+        if (self.error.is_some()) { return; }
+        if let Err(err) = writeln!(self.writer, "{}", event) { self.error = Some(err); }
+    }
+}
+
+impl Executor {
+
+    /// Starts writing a trace of every change to the queue to `writer`,
+    ///  beginning with the queue as it is now.
+    pub fn start_trace(&mut self, writer : impl Write + Send + 'static) {
+        let mut tracer = Tracer { writer : Box::new(writer), error : None };
+        if let Err(err) = writeln!(tracer.writer, "{}", HEADER) { tracer.error = Some(err); }
+        self.tracer = Some(tracer);
+        self.trace_state();
+    }
+
+    /// Stops tracing, returning the first error that writing the trace hit.
+    pub fn stop_trace(&mut self) -> io::Result<()> {
+        let Some(mut tracer) = self.tracer.take()
+            else { return Ok(()); };
+        if let Some(err) = tracer.error { return Err(err); }
+        tracer.writer.flush()
+    }
+
+    /// Records an event, only building it if a trace is being written.
+    pub(super) fn trace(&mut self, event : impl FnOnce() -> TraceEvent) {
+        if let Some(tracer) = &mut self.tracer { tracer.write(&event()); }
+    }
+
+    /// Records the whole state of the queue.
+    pub(super) fn trace_state(&mut self) {
+        let Some(tracer) = &mut self.tracer
+            else { return; };
+        tracer.write(&TraceEvent::Reset { ticks : self.ticks });
+        tracer.write(&TraceEvent::Latest(self.latest_expr.clone()));
+        for expr in &self.queued_exprs { tracer.write(&TraceEvent::Push(expr.clone())); }
+        for queue in &self.named_queues {
+            tracer.write(&TraceEvent::Create(queue.name.clone()));
+            if (queue.scheduled) { tracer.write(&TraceEvent::Schedule(queue.name.clone(), true)); }
+            for expr in &queue.exprs {
+                tracer.write(&TraceEvent::In(queue.name.clone(), Box::new(TraceEvent::Push(expr.clone()))));
+            }
+        }
+    }
+
+    pub(super) fn trace_result(&mut self, value : &Value) {
+        self.trace(|| TraceEvent::Result(value.to_string()));
+    }
+
+}
+
+
+/// Why a trace couldn't be read.
+#[derive(Debug)]
+pub enum TraceError {
+    Io(io::Error),
+    /// `line` is 1-indexed.
+    Format { line : usize, message : String }
+}
+
+impl From<io::Error> for TraceError {
+    fn from(err : io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self) {
+            Self::Io     (err)             => write!(f, "{}", err),
+            Self::Format { line, message } => write!(f, "line {} of the trace: {}", line, message)
+        }
+    }
+}
+
+impl Error for TraceError { }
+
+
+/// A trace that has been read back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub events : Vec<TraceEvent>
+}
+
+impl Trace {
+
+    pub fn read(reader : impl BufRead) -> Result<Self, TraceError> {
+        let mut lines = reader.lines();
+        match (lines.next().transpose()?) {
+            Some(header) if (header == HEADER) => { },
+            _                                  => {
+                return Err(TraceError::Format { line : 1, message : format!("expected {:?}", HEADER) });
+            }
+        }
+        let mut events = Vec::new();
+        for (i, line,) in lines.enumerate() {
+            let line = line?;
+            if (line.is_empty()) { continue; }
+            events.push(TraceEvent::parse(&line).map_err(|message| TraceError::Format { line : i + 2, message })?);
+        }
+        Ok(Self { events })
+    }
+
+    /// The ticks that were executed, with what each one evaluated to.
+    pub fn ticks(&self) -> impl Iterator<Item = (u64, &Expr, Option<&str>)> {
+        self.events.iter().enumerate().filter_map(|(i, event,)| {
+            let (tick, expr,) = event.tick()?;
+            Some((tick, expr, self.events[(i + 1)..].iter()
+                .take_while(|event| event.tick().is_none())
+                .find_map(|event| match (event) {
+                    TraceEvent::Result(value) => Some(value.as_str()),
+                    _                         => None
+                })))
+        })
+    }
+
+    /// Replays the trace into a new executor, up to and including everything
+    ///  that happened during `tick`. Returns `None` if the trace doesn't cover
+    ///  that tick.
+    pub fn executor_at(&self, tick : u64) -> Option<Executor> {
+        let mut e       = Executor::new();
+        let mut started = false;
+        for event in &self.events {
+            match (event) {
+                TraceEvent::Reset    { ticks }     => {
+                    if (*ticks > tick) { break; }
+                    started = true;
+                    e.ticks = *ticks;
+                    e.queued_exprs.clear();
+                    e.named_queues.clear();
+                },
+                TraceEvent::Latest   (expr)        => { e.latest_expr = expr.clone(); },
+                TraceEvent::Push     (expr)        => { e.push_exprs([expr.clone()]); },
+                TraceEvent::Insert   (index, expr) => {
+                    // Inserting at index 0 is recorded as inserting the
+                    //  latest expression at 1, which may be the end.
+                    if ((1..=(e.queued_exprs.len() + 1)).contains(index)) { e.queued_exprs.insert(index - 1, expr.clone()); }
+                },
+                TraceEvent::Set      (index, expr) => { let _ = e.set_expr(*index, expr.clone()); },
+                TraceEvent::Tick     (n, expr)     => {
+                    if (*n > tick) { break; }
+                    e.queued_exprs.pop_front();
+                    e.latest_expr = expr.clone();
+                    e.ticks       = *n;
+                    e.turn        = 1;
+                },
+                TraceEvent::Requeue  (expr)        => {
+                    e.queued_exprs.push_front(expr.clone());
+                    e.ticks  = e.ticks.saturating_sub(1);
+                    e.turn   = 0;
+                },
+                TraceEvent::Result   (_)           => { },
+                TraceEvent::Take                   => { e.queued_exprs.pop_front(); },
+                TraceEvent::Create   (name)        => { e.create_queue(name); },
+                TraceEvent::Schedule (name, on)    => { let _ = e.schedule(name, *on); },
+                TraceEvent::In       (name, event) => {
+                    let Some(index) = e.named_index(name)
+                        else { continue; };
+                    match (&**event) {
+                        TraceEvent::Push   (expr)      => { let _ = e.push_named(name, [expr.clone()]); },
+                        TraceEvent::Insert (i, expr)   => { let _ = e.insert_named(name, *i, expr.clone()); },
+                        TraceEvent::Set    (i, expr)   => { let _ = e.set_named(name, *i, expr.clone()); },
+                        TraceEvent::Take               => { e.named_queues[index].exprs.pop_front(); },
+                        TraceEvent::Tick   (n, expr)   => {
+                            if (*n > tick) { break; }
+                            e.named_queues[index].exprs.pop_front();
+                            e.latest_expr = expr.clone();
+                            e.ticks       = *n;
+                            e.turn        = index + 2;
+                        },
+                        TraceEvent::Requeue(expr)      => {
+                            e.named_queues[index].exprs.push_front(expr.clone());
+                            e.ticks  = e.ticks.saturating_sub(1);
+                            e.turn   = index + 1;
+                        },
+                        _                              => { }
+                    }
+                }
+            }
+        }
+        (started && e.ticks == tick).then_some(e)
+    }
+
+    /// The queue after `tick`, starting with the expression executed last.
+    pub fn queue_at(&self, tick : u64) -> Option<Vec<Expr>> {
+        let e = self.executor_at(tick)?;
+        Some((0..e.len_exprs()).map(|i| e.get_expr(i).unwrap().clone()).collect())
+    }
+
+}
@@ -1,125 +1,237 @@
+use crate::exec::{ RuntimeError, ErrorKind };
 use core::fmt;
 use core::ops::{ Add, Sub, Mul, Div, Rem, Not };
 use f128::f128;
 use std::cmp::Ordering;
 
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum Value {
     Unit,
     Bool(bool),
     Int(i128),
     Float(f128),
     String(String),
-    Error,
+    Error(Box<RuntimeError>),
     ExprQueue,
+    /// A queue made by `mkqueue`, by its name.
+    NamedQueue(String),
+    /// A channel made by `channel`, by its name.
+    Channel(String),
     Array(Vec<Value>)
 }
 
-impl Add for Value {
-    type Output = Value;
-    fn add(self, rhs : Self) -> Self::Output {
-        match ((self, rhs)) {
-            (Self::Unit, _)                  | (_, Self::Unit)                 => Self::Error,
+/// What int arithmetic does when the result doesn't fit in an int.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// The expression evaluates to an `overflow` error.
+    #[default]
+    Error,
+    /// The result wraps around, from the largest int to the smallest.
+    Wrap,
+    /// The result stays at the largest or smallest int.
+    Saturate
+}
+
+impl Overflow {
+    fn int(self, l : i128, r : i128, checked : fn(i128, i128) -> Option<i128>, wrapping : fn(i128, i128) -> i128, saturating : fn(i128, i128) -> i128) -> Value {
+        let result = match (self) {
+            Self::Error    => checked(l, r),
+            Self::Wrap     => Some(wrapping(l, r)),
+            Self::Saturate => Some(saturating(l, r))
+        };
+        result.map_or_else(|| Value::overflowed(l, r), Value::Int)
+    }
+}
+
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match (self) {
+            Self::Unit          => "unit",
+            Self::Bool(_)       => "bool",
+            Self::Int(_)        => "int",
+            Self::Float(_)      => "float",
+            Self::String(_)     => "string",
+            Self::Error(_)      => "error",
+            Self::ExprQueue     => "queue",
+            Self::NamedQueue(_) => "queue",
+            Self::Channel(_)    => "channel",
+            Self::Array(_)      => "array"
+        }
+    }
+
+    fn mismatch(op : &str, l : &Value, r : &Value) -> Self {
+        RuntimeError::new(ErrorKind::TypeMismatch, format!("cannot {} {} and {}", op, l.type_name(), r.type_name()))
+            .with_operands([l.clone(), r.clone()])
+            .into()
+    }
+
+    fn negative_repeat(l : &Value, r : &Value) -> Self {
+        RuntimeError::new(ErrorKind::OutOfRange, "cannot repeat a negative number of times")
+            .with_operands([l.clone(), r.clone()])
+            .into()
+    }
+
+    fn overflowed(l : i128, r : i128) -> Self {
+        RuntimeError::new(ErrorKind::Overflow, "the result is too large for an int")
+            .with_operands([Value::Int(l), Value::Int(r)])
+            .into()
+    }
+
+    fn division_by_zero(l : &Value, r : &Value) -> Self {
+        RuntimeError::new(ErrorKind::DivisionByZero, "division by zero")
+            .with_operands([l.clone(), r.clone()])
+            .into()
+    }
+}
+
+impl Value {
+    /// Adds like `+`, with `overflow` deciding what happens to an int result
+    ///  that doesn't fit.
+    pub fn add_with(self, rhs : Self, overflow : Overflow) -> Self {
+        match (self, rhs) {
+            (e@Self::Error(_), _)            | (_, e@Self::Error(_))           => e,
+            (a@Self::Unit, b)                | (a, b@Self::Unit)               => Self::mismatch("add", &a, &b),
             (Self::Bool(a), Self::Bool(b))                                     => Self::Bool(a || b),
-            (Self::Bool(a), Self::Int(b))    | (Self::Int(b), Self::Bool(a))   => Self::Int((a as i128) + b),
+            (Self::Bool(a), Self::Int(b))    | (Self::Int(b), Self::Bool(a))   => overflow.int(a as i128, b, i128::checked_add, i128::wrapping_add, i128::saturating_add),
             (Self::Bool(a), Self::Float(b))  | (Self::Float(b), Self::Bool(a)) => Self::Float(f128::from(a as i128) + b),
-            (Self::Int(a), Self::Int(b))                                       => Self::Int(a + b),
+            (Self::Int(a), Self::Int(b))                                       => overflow.int(a, b, i128::checked_add, i128::wrapping_add, i128::saturating_add),
             (Self::Int(a), Self::Float(b))   | (Self::Float(b), Self::Int(a))  => Self::Float(f128::from(a) + b),
             (Self::Float(a), Self::Float(b))                                   => Self::Float(a + b),
             (a@Self::String(_), b)           | (a, b@Self::String(_))          => Self::String(format!("{}{}", a, b)),
-            (Self::Error, _)                 | (_, Self::Error)                => Self::Error,
-            (Self::ExprQueue, _)             | (_, Self::ExprQueue)            => Self::Error,
+            (a@Self::ExprQueue, b)           | (a, b@Self::ExprQueue)          => Self::mismatch("add", &a, &b),
+            (a@Self::NamedQueue(_), b)       | (a, b@Self::NamedQueue(_))      => Self::mismatch("add", &a, &b),
+            (a@Self::Channel(_), b)          | (a, b@Self::Channel(_))         => Self::mismatch("add", &a, &b),
             (Self::Array(mut a), Self::Array(mut b))                           => Self::Array( {
                 a.append(&mut b);
                 a
             } ),
-            (Self::Array(_), _)              | (_, Self::Array(_))             => Self::Error,
+            (a@Self::Array(_), b)            | (a, b@Self::Array(_))           => Self::mismatch("add", &a, &b),
         }
     }
 }
 
-impl Sub for Value {
+impl Add for Value {
     type Output = Value;
-    fn sub(self, rhs : Self) -> Self::Output {
-        match ((&self, &rhs)) {
-            (Self::Unit, _)                  | (_, Self::Unit)      => Self::Error,
-            (Self::Bool(a), Self::Bool(b))                          => Self::Bool(*a && (! *b)),
-            (Self::Bool(a), Self::Int(b))                           => Self::Int((*a as i128) - b),
-            (Self::Bool(a), Self::Float(b))                         => Self::Float(f128::from(*a as i128) - b),
-            (Self::Int(a), Self::Bool(b))                           => Self::Int(*a - (*b as i128)),
-            (Self::Int(a), Self::Int(b))                            => Self::Int(*a - *b),
-            (Self::Int(a), Self::Float(b))                          => Self::Float(f128::from(*a) - b),
-            (Self::Float(a), Self::Bool(b))                         => Self::Float(*a - f128::from(*b as i128)),
-            (Self::Float(a), Self::Int(b))                          => Self::Float(*a - f128::from(*b)),
-            (Self::Float(a), Self::Float(b))                        => Self::Float(*a - *b),
-            (Self::String(_), _)             | (_, Self::String(_)) => Self::Error,
-            (Self::Error, _)                 | (_, Self::Error)     => Self::Error,
-            (Self::ExprQueue, _)             | (_, Self::ExprQueue) => Self::Error,
-            (Self::Array(_), _)              | (_, Self::Array(_))  => Self::Error
+    fn add(self, rhs : Self) -> Self::Output {
+        self.add_with(rhs, Overflow::default())
+    }
+}
+
+impl Value {
+    /// Subtracts like `-`, under the given overflow policy.
+    pub fn sub_with(self, rhs : Self, overflow : Overflow) -> Self {
+        match (&self, &rhs) {
+            (Self::Error(e), _)              | (_, Self::Error(e))      => Self::Error(e.clone()),
+            (Self::Unit, _)                  | (_, Self::Unit)          => Self::mismatch("subtract", &self, &rhs),
+            (Self::Bool(a), Self::Bool(b))                              => Self::Bool(*a && (! *b)),
+            (Self::Bool(a), Self::Int(b))                               => overflow.int(*a as i128, *b, i128::checked_sub, i128::wrapping_sub, i128::saturating_sub),
+            (Self::Bool(a), Self::Float(b))                             => Self::Float(f128::from(*a as i128) - b),
+            (Self::Int(a), Self::Bool(b))                               => overflow.int(*a, *b as i128, i128::checked_sub, i128::wrapping_sub, i128::saturating_sub),
+            (Self::Int(a), Self::Int(b))                                => overflow.int(*a, *b, i128::checked_sub, i128::wrapping_sub, i128::saturating_sub),
+            (Self::Int(a), Self::Float(b))                              => Self::Float(f128::from(*a) - b),
+            (Self::Float(a), Self::Bool(b))                             => Self::Float(*a - f128::from(*b as i128)),
+            (Self::Float(a), Self::Int(b))                              => Self::Float(*a - f128::from(*b)),
+            (Self::Float(a), Self::Float(b))                            => Self::Float(*a - *b),
+            (Self::String(_), _)             | (_, Self::String(_))     => Self::mismatch("subtract", &self, &rhs),
+            (Self::ExprQueue, _)             | (_, Self::ExprQueue)     => Self::mismatch("subtract", &self, &rhs),
+            (Self::NamedQueue(_), _)         | (_, Self::NamedQueue(_)) => Self::mismatch("subtract", &self, &rhs),
+            (Self::Channel(_), _)            | (_, Self::Channel(_))    => Self::mismatch("subtract", &self, &rhs),
+            (Self::Array(_), _)              | (_, Self::Array(_))      => Self::mismatch("subtract", &self, &rhs)
         }
     }
 }
 
-impl Mul for Value {
+impl Sub for Value {
     type Output = Value;
-    fn mul(self, rhs : Self) -> Self::Output {
-        match ((&self, &rhs)) {
-            (Self::Unit, _)                  | (_, Self::Unit)                 => Self::Error,
+    fn sub(self, rhs : Self) -> Self::Output {
+        self.sub_with(rhs, Overflow::default())
+    }
+}
+
+impl Value {
+    /// Multiplies like `*`, under the given overflow policy.
+    pub fn mul_with(self, rhs : Self, overflow : Overflow) -> Self {
+        match (&self, &rhs) {
+            (Self::Error(e), _)              | (_, Self::Error(e))             => Self::Error(e.clone()),
+            (Self::Unit, _)                  | (_, Self::Unit)                 => Self::mismatch("multiply", &self, &rhs),
             (Self::Bool(a), Self::Bool(b))                                     => Self::Bool(*a && *b),
             (Self::Bool(a), Self::Int(b))    | (Self::Int(b), Self::Bool(a))   => Self::Int((*a as i128) * b),
             (Self::Bool(a), Self::Float(b))  | (Self::Float(b), Self::Bool(a)) => Self::Float(f128::from(*a as i128) * b),
-            (Self::Int(a), Self::Int(b))                                       => Self::Int(*a * *b),
+            (Self::Int(a), Self::Int(b))                                       => overflow.int(*a, *b, i128::checked_mul, i128::wrapping_mul, i128::saturating_mul),
             (Self::Int(a), Self::Float(b))   | (Self::Float(b), Self::Int(a))  => Self::Float(f128::from(*a) * *b),
             (Self::Float(a), Self::Float(b))                                   => Self::Float(*a * *b),
-            (Self::String(a), Self::Int(b))  | (Self::Int(b), Self::String(a)) => if (*b >= 0) { Self::String(a.repeat(*b as usize)) } else { Self::Error },
-            (Self::String(_), _)             | (_, Self::String(_))            => Self::Error,
-            (Self::Error, _)                 | (_, Self::Error)                => Self::Error,
-            (Self::ExprQueue, _)             | (_, Self::ExprQueue)            => Self::Error,
+            (Self::String(_), Self::Int(b))  | (Self::Int(b), Self::String(_))
+            | (Self::Array(_), Self::Int(b)) | (Self::Int(b), Self::Array(_))  if (*b < 0) => Self::negative_repeat(&self, &rhs),
+            (Self::String(a), Self::Int(b))  | (Self::Int(b), Self::String(a)) => Self::String(a.repeat(*b as usize)),
+            (Self::String(_), _)             | (_, Self::String(_))            => Self::mismatch("multiply", &self, &rhs),
+            (Self::ExprQueue, _)             | (_, Self::ExprQueue)            => Self::mismatch("multiply", &self, &rhs),
+            (Self::NamedQueue(_), _)         | (_, Self::NamedQueue(_))        => Self::mismatch("multiply", &self, &rhs),
+            (Self::Channel(_), _)            | (_, Self::Channel(_))           => Self::mismatch("multiply", &self, &rhs),
             (Self::Array(a), Self::Int(b))   | (Self::Int(b), Self::Array(a))  => {
                 let b = *b as usize;
                 let mut out = Vec::with_capacity(a.len() * b);
                 for _ in 0..b { out.extend(a.iter().cloned()); }
                 Self::Array(out)
             },
-            (Self::Array(_), _)              | (_, Self::Array(_))             => Self::Error
+            (Self::Array(_), _)              | (_, Self::Array(_))             => Self::mismatch("multiply", &self, &rhs)
         }
     }
 }
 
-impl Div for Value {
+impl Mul for Value {
     type Output = Value;
-    fn div(self, rhs : Self) -> Self::Output {
-        match ((&self, &rhs)) {
-            (Self::Unit, _)                  | (_, Self::Unit)                 => Self::Error,
-            (Self::Bool(a), Self::Bool(b))                                     => if (*b ) { Self::Bool(*a) } else { Self::Error }, 
-            (Self::Bool(_), Self::Int(_))                                      => Self::Error, 
-            (Self::Int(a), Self::Bool(b))                                      => if (*b) { Self::Int(*a) } else { Self::Error }, 
-            (Self::Bool(_), Self::Float(_))                                    => Self::Error, 
-            (Self::Float(a), Self::Bool(b))                                    => if (*b) { Self::Float(*a) } else { Self::Error }, 
-            (Self::Int(a), Self::Int(b))                                       => Self::Int(*a / *b), 
+    fn mul(self, rhs : Self) -> Self::Output {
+        self.mul_with(rhs, Overflow::default())
+    }
+}
+
+impl Value {
+    /// Divides like `/`. Only dividing the smallest int by -1 overflows.
+    pub fn div_with(self, rhs : Self, overflow : Overflow) -> Self {
+        match (&self, &rhs) {
+            (Self::Error(e), _)              | (_, Self::Error(e))             => Self::Error(e.clone()),
+            (Self::Unit, _)                  | (_, Self::Unit)                 => Self::mismatch("divide", &self, &rhs),
+            (Self::Bool(a), Self::Bool(b))                                     => if (*b ) { Self::Bool(*a) } else { Self::division_by_zero(&self, &rhs) }, 
+            (Self::Bool(_), Self::Int(_))                                      => Self::mismatch("divide", &self, &rhs), 
+            (Self::Int(a), Self::Bool(b))                                      => if (*b) { Self::Int(*a) } else { Self::division_by_zero(&self, &rhs) }, 
+            (Self::Bool(_), Self::Float(_))                                    => Self::mismatch("divide", &self, &rhs), 
+            (Self::Float(a), Self::Bool(b))                                    => if (*b) { Self::Float(*a) } else { Self::division_by_zero(&self, &rhs) }, 
+            (Self::Int(_), Self::Int(0))                                       => Self::division_by_zero(&self, &rhs),
+            (Self::Int(a), Self::Int(b))                                       => overflow.int(*a, *b, i128::checked_div, i128::wrapping_div, i128::saturating_div),
             (Self::Int(a), Self::Float(b))                                     => Self::Float(f128::from(*a) / *b), 
             (Self::Float(a), Self::Int(b))                                     => Self::Float(*a / f128::from(*b)), 
             (Self::Float(a), Self::Float(b))                                   => Self::Float(*a / *b), 
-            (Self::String(_), _)             | (_, Self::String(_))            => Self::Error,
-            (Self::Error, _)                 | (_, Self::Error)                => Self::Error,
-            (Self::ExprQueue, _)             | (_, Self::ExprQueue)            => Self::Error,
-            (Self::Array(_), _)              | (_, Self::Array(_))             => Self::Error
+            (Self::String(_), _)             | (_, Self::String(_))            => Self::mismatch("divide", &self, &rhs),
+            (Self::ExprQueue, _)             | (_, Self::ExprQueue)            => Self::mismatch("divide", &self, &rhs),
+            (Self::NamedQueue(_), _)         | (_, Self::NamedQueue(_))        => Self::mismatch("divide", &self, &rhs),
+            (Self::Channel(_), _)            | (_, Self::Channel(_))           => Self::mismatch("divide", &self, &rhs),
+            (Self::Array(_), _)              | (_, Self::Array(_))             => Self::mismatch("divide", &self, &rhs)
         }
     }
 }
 
-impl Not for Value {
+impl Div for Value {
     type Output = Value;
-    fn not(self) -> Self::Output {
+    fn div(self, rhs : Self) -> Self::Output {
+        self.div_with(rhs, Overflow::default())
+    }
+}
+
+impl Value {
+    /// Negates like `!`. Only negating the smallest int overflows.
+    pub fn not_with(self, overflow : Overflow) -> Self {
         match (self) {
             Self::Bool(b) => Self::Bool(!b),
-            Self::Unit => Self::Error,
-            Self::Int(i) => Self::Int(-i),
+            Self::Unit => RuntimeError::type_mismatch("a negatable value", self).into(),
+            Self::Int(i) => overflow.int(0, i, i128::checked_sub, i128::wrapping_sub, i128::saturating_sub),
             Self::Float(f) => Self::Float(-f),
             Self::String(s) => Self::String(s.chars().rev().collect::<String>()),
-            Self::Error => Self::Unit, // TODO: yes, we're doing this
-            Self::ExprQueue => Self::Error,
+            Self::Error(_) => Self::Unit, // TODO: yes, we're doing this
+            Self::ExprQueue => RuntimeError::type_mismatch("a negatable value", self).into(),
+            Self::NamedQueue(_) => RuntimeError::type_mismatch("a negatable value", self).into(),
+            Self::Channel(_) => RuntimeError::type_mismatch("a negatable value", self).into(),
             Self::Array(mut arr) => {
                 arr.reverse();
                 Self::Array(arr)
@@ -128,31 +240,63 @@ impl Not for Value {
     }
 }
 
+impl Not for Value {
+    type Output = Value;
+    fn not(self) -> Self::Output {
+        self.not_with(Overflow::default())
+    }
+}
+
 impl Rem for Value {
     type Output = Value;
     fn rem(self, rhs : Self) -> Self::Output {
-        match ((&self, &rhs)) {
-            (Self::Unit, _)                  | (_, Self::Unit)                 => Self::Error,
-            (Self::Bool(_), _)               | (_, Self::Bool(_))              => Self::Error,
-            (Self::Int(a), Self::Int(b))                                       => Self::Int(*a % *b), 
+        match (&self, &rhs) {
+            (Self::Error(e), _)              | (_, Self::Error(e))             => Self::Error(e.clone()),
+            (Self::Unit, _)                  | (_, Self::Unit)                 => Self::mismatch("take the remainder of", &self, &rhs),
+            (Self::Bool(_), _)               | (_, Self::Bool(_))              => Self::mismatch("take the remainder of", &self, &rhs),
+            (Self::Int(_), Self::Int(0))                                       => Self::division_by_zero(&self, &rhs),
+            // Only `MIN % -1` overflows, and wrapping gives its exact result, 0.
+            (Self::Int(a), Self::Int(b))                                       => Self::Int(a.wrapping_rem(*b)),
             (Self::Int(a), Self::Float(b))                                     => Self::Float(f128::from(*a) % *b), 
             (Self::Float(a), Self::Int(b))                                     => Self::Float(*a % f128::from(*b)), 
             (Self::Float(a), Self::Float(b))                                   => Self::Float(*a % *b), 
-            (Self::String(_), _)             | (_, Self::String(_))            => Self::Error,
-            (Self::Error, _)                 | (_, Self::Error)                => Self::Error,
-            (Self::ExprQueue, _)             | (_, Self::ExprQueue)            => Self::Error,
-            (Self::Array(_), _)              | (_, Self::Array(_))             => Self::Error
+            (Self::String(_), _)             | (_, Self::String(_))            => Self::mismatch("take the remainder of", &self, &rhs),
+            (Self::ExprQueue, _)             | (_, Self::ExprQueue)            => Self::mismatch("take the remainder of", &self, &rhs),
+            (Self::NamedQueue(_), _)         | (_, Self::NamedQueue(_))        => Self::mismatch("take the remainder of", &self, &rhs),
+            (Self::Channel(_), _)            | (_, Self::Channel(_))           => Self::mismatch("take the remainder of", &self, &rhs),
+            (Self::Array(_), _)              | (_, Self::Array(_))             => Self::mismatch("take the remainder of", &self, &rhs)
+        }
+    }
+}
+
+// Errors equal each other when the same kind of thing went wrong, whatever
+//  the message, expression or operands, so that `=` can tell failures apart
+//  without depending on where they happened.
+impl PartialEq for Value {
+    fn eq(&self, rhs : &Self) -> bool {
+        match (self, rhs,) {
+            (Self::Unit, Self::Unit)                     => true,
+            (Self::Bool(l), Self::Bool(r))               => l == r,
+            (Self::Int(l), Self::Int(r))                 => l == r,
+            (Self::Float(l), Self::Float(r))             => l == r,
+            (Self::String(l), Self::String(r))           => l == r,
+            (Self::Error(l), Self::Error(r))             => l.kind == r.kind,
+            (Self::ExprQueue, Self::ExprQueue)           => true,
+            (Self::NamedQueue(l), Self::NamedQueue(r))   => l == r,
+            (Self::Channel(l), Self::Channel(r))         => l == r,
+            (Self::Array(l), Self::Array(r))             => l == r,
+            _                                            => false
         }
     }
 }
 
 impl PartialOrd for Value {
     fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
-        match ((&self, &rhs)) {
-            (Self::Bool(l), Self::Bool(r))     => Some(l.cmp(&r)),
-            (Self::Int(l), Self::Int(r))       => Some(l.cmp(&r)),
-            (Self::Float(l), Self::Float(r))   => l.partial_cmp(&r),
-            (Self::String(l), Self::String(r)) => Some(l.cmp(&r)),
+        match (self, rhs) {
+            (Self::Bool(l), Self::Bool(r))     => Some(l.cmp(r)),
+            (Self::Int(l), Self::Int(r))       => Some(l.cmp(r)),
+            (Self::Float(l), Self::Float(r))   => l.partial_cmp(r),
+            (Self::String(l), Self::String(r)) => Some(l.cmp(r)),
             _                                  => None,
         }
     }
@@ -161,14 +305,16 @@ impl PartialOrd for Value {
 impl fmt::Display for Value {
     fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
         match (self) {
-            Self::Unit      => write!(f, "unit"),
-            Self::Bool(v)   => if (*v) { write!(f, "true") } else { write!(f, "false") },
-            Self::Int(v)    => write!(f, "{}", v),
-            Self::Float(v)  => write!(f, "{}", v),
-            Self::String(v) => write!(f, "{}", v),
-            Self::Error     => write!(f, "error"),
-            Self::ExprQueue => write!(f, "exprqueue"),
-            Self::Array(v)  => {
+            Self::Unit             => write!(f, "unit"),
+            Self::Bool(v)          => if (*v) { write!(f, "true") } else { write!(f, "false") },
+            Self::Int(v)           => write!(f, "{}", v),
+            Self::Float(v)         => write!(f, "{}", v),
+            Self::String(v)        => write!(f, "{}", v),
+            Self::Error(e)         => write!(f, "error({})", e),
+            Self::ExprQueue        => write!(f, "exprqueue"),
+            Self::NamedQueue(name) => write!(f, "exprqueue({})", name),
+            Self::Channel(name)    => write!(f, "channel({})", name),
+            Self::Array(v)         => {
                 write!(f, "[")?;
                 for (i, u,) in v.iter().enumerate() {
                     if (i != 0) { write!(f, ", ")?; }
@@ -0,0 +1,140 @@
+//! Everything a script reads or writes goes through a [`FileSystem`] owned by
+//!  its [`Executor`](crate::exec::Executor).
+
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ self, ErrorKind };
+use std::path::{ Component, Path, PathBuf };
+use std::sync::{ Arc, Mutex };
+
+
+pub trait FileSystem : Send {
+    fn read(&mut self, path : &str) -> io::Result<Vec<u8>>;
+
+    /// Creates the file if it doesn't exist, and replaces its contents if it
+    ///  does.
+    fn write(&mut self, path : &str, contents : &[u8]) -> io::Result<()>;
+
+    fn read_to_string(&mut self, path : &str) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|_| io::Error::new(ErrorKind::InvalidData, "file is not valid UTF-8"))
+    }
+}
+
+
+/// Whether scripts may write files, or only read them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    ReadOnly,
+    ReadWrite
+}
+
+
+/// The real filesystem, with paths relative to the working directory.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Disk;
+
+impl FileSystem for Disk {
+    fn read(&mut self, path : &str) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write(&mut self, path : &str, contents : &[u8]) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+}
+
+
+/// The real filesystem, but only below a root directory. Paths are taken
+///  relative to the root, even if they start with `/`, and may not contain
+///  `..` or lead out of the root through a symlink.
+#[derive(Debug, Clone)]
+pub struct Jail {
+    root : PathBuf
+}
+
+impl Jail {
+    pub fn new(root : impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self { root : fs::canonicalize(root)? })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn resolve(&self, path : &str) -> io::Result<PathBuf> {
+        let mut resolved = self.root.clone();
+        for component in Path::new(path).components() {
+            match (component) {
+                Component::Normal(part)                     => resolved.push(part),
+                Component::CurDir | Component::RootDir      => { },
+                Component::ParentDir | Component::Prefix(_) => { return Err(escape(path)); }
+            }
+        }
+        // Symlinks are followed as far as the path exists, and wherever that
+        //  leads has to still be inside the root.
+        let mut existing = resolved.as_path();
+        let real = loop {
+            match (fs::canonicalize(existing)) {
+                Ok(real)                                        => break real,
+                // A dangling symlink could still be written through.
+                Err(_) if (existing.is_symlink())               => { return Err(escape(path)); },
+                Err(err) if (err.kind() == ErrorKind::NotFound) => { existing = existing.parent().ok_or(err)?; },
+                Err(err)                                        => { return Err(err); }
+            }
+        };
+        if (! real.starts_with(&self.root)) { return Err(escape(path)); }
+        let missing = resolved.strip_prefix(existing).unwrap();
+        if (missing.as_os_str().is_empty()) { Ok(real) }
+        else { Ok(real.join(missing)) }
+    }
+}
+
+fn escape(path : &str) -> io::Error {
+    io::Error::new(ErrorKind::PermissionDenied, format!("{} is outside the jail", path))
+}
+
+impl FileSystem for Jail {
+    fn read(&mut self, path : &str) -> io::Result<Vec<u8>> {
+        fs::read(self.resolve(path)?)
+    }
+
+    fn write(&mut self, path : &str, contents : &[u8]) -> io::Result<()> {
+        fs::write(self.resolve(path)?, contents)
+    }
+}
+
+
+/// Files kept in memory, by exact path. Clones share the same files, so one
+///  can be given to an [`Executor`](crate::exec::Executor) and another kept to
+///  look at what it wrote.
+#[derive(Debug, Clone, Default)]
+pub struct Memory {
+    files : Arc<Mutex<HashMap<String, Vec<u8>>>>
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, path : impl Into<String>, contents : impl Into<Vec<u8>>) {
+        self.files.lock().unwrap().insert(path.into(), contents.into());
+    }
+
+    pub fn get(&self, path : &str) -> Option<Vec<u8>> {
+        self.files.lock().unwrap().get(path).cloned()
+    }
+}
+
+impl FileSystem for Memory {
+    fn read(&mut self, path : &str) -> io::Result<Vec<u8>> {
+        self.get(path).ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("{} does not exist", path)))
+    }
+
+    fn write(&mut self, path : &str, contents : &[u8]) -> io::Result<()> {
+        self.insert(path, contents);
+        Ok(())
+    }
+}
@@ -1,74 +1,74 @@
+use crate::builtin::Builtin;
+use crate::span::Span;
 use core::fmt;
 use f128::f128;
 
 
 #[derive(Debug, Clone)]
-pub enum Expr {
-    Print(Box<Expr>), // message
-    Add(Box<(Expr, Expr,)>), // left, right
-    Sub(Box<(Expr, Expr,)>), // left, right
-    Mul(Box<(Expr, Expr,)>), // left, right
-    Div(Box<(Expr, Expr,)>), // left, right
-    Rem(Box<(Expr, Expr,)>),
-    Not(Box<(Expr,)>), 
-    Equals(Box<(Expr, Expr)>),
-    Greater(Box<(Expr, Expr)>),
-    GreaterEquals(Box<(Expr, Expr)>),
-    Less(Box<(Expr, Expr)>),
-    LessEquals(Box<(Expr, Expr)>),
-    Get(Box<(Expr, Expr,)>), // iterable, index
-    Gets(Box<(Expr, Expr, Expr)>), // iterable, start index, end index
-    Push(Box<(Expr, Expr,)>), // iterable, entry
-    Pushes(Box<(Expr, Expr)>), // iterable, iterable
-    Insert(Box<(Expr, Expr, Expr,)>), // iterable, index, entry
-    Inserts(Box<(Expr, Expr, Expr)>), // iterable, start index, iterable
-    Set(Box<(Expr, Expr, Expr)>), // iterable, index, entry
-    Sets(Box<(Expr, Expr, Expr)>), // iterable, start index, iterable
-    Len(Box<Expr>), // iterable
-    FSRead(Box<Expr>), // file name
-    If(Box<(Expr, Expr, Expr)>), // conditional. runs second expr if the first is true, otherwise runs the third
-    Range(Box<(Expr, Expr)>), // range from first to second, inclusive of first but not second
-    Str(Box<Expr>),
-    Int(Box<Expr>),
-    Lit(Lit), // value
+pub struct Expr {
+    pub kind : ExprKind,
+    pub span : Span
+}
+
+impl Expr {
+    pub fn new(kind : ExprKind, span : Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl From<ExprKind> for Expr {
+    fn from(kind : ExprKind) -> Self {
+        Self::new(kind, Span::unknown())
+    }
+}
+
+// Spans only describe where an expression was written, not what it does.
+impl PartialEq for Expr {
+    fn eq(&self, other : &Self) -> bool {
+        self.kind == other.kind
+    }
 }
 
 impl fmt::Display for Expr {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Builtin(Builtin, Vec<Expr>), // builtin, arguments in order
+    Lit(Lit), // value
+}
+
+impl ExprKind {
+    /// Whether a variadic builtin ends this expression, taking any arguments
+    ///  written after it.
+    fn takes_rest(&self) -> bool {
+        matches!(self, ExprKind::Builtin(builtin, args) if (builtin.variadic() || args.last().is_some_and(|arg| arg.kind.takes_rest())))
+    }
+}
+
+impl fmt::Display for ExprKind {
     fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
         match (self) {
-            Expr::Print          (expr) => write!(f, "print {}", expr),
-            Expr::Add            (expr) => write!(f, "+ {} {}", expr.0, expr.1),
-            Expr::Sub            (expr) => write!(f, "- {} {}", expr.0, expr.1),
-            Expr::Mul            (expr) => write!(f, "* {} {}", expr.0, expr.1),
-            Expr::Div            (expr) => write!(f, "/ {} {}", expr.0, expr.1),
-            Expr::Rem            (expr) => write!(f, "% {} {}", expr.0, expr.1),
-            Expr::Not            (expr) => write!(f, "! {}", expr.0),
-            Expr::Equals         (expr) => write!(f, "= {} {}", expr.0, expr.1),
-            Expr::Greater        (expr) => write!(f, "> {} {}", expr.0, expr.1),
-            Expr::GreaterEquals  (expr) => write!(f, ">= {} {}", expr.0, expr.1),
-            Expr::Less           (expr) => write!(f, "< {} {}", expr.0, expr.1),
-            Expr::LessEquals     (expr) => write!(f, "<= {} {}", expr.0, expr.1),
-            Expr::Get            (expr) => write!(f, "get {} {}", expr.0, expr.1),
-            Expr::Gets           (expr) => write!(f, "gets {} {} {}", expr.0, expr.1, expr.2),
-            Expr::Push           (expr) => write!(f, "push {} {}", expr.0, expr.1),
-            Expr::Pushes         (expr) => write!(f, "pushes {} {}", expr.0, expr.1),
-            Expr::Insert         (expr) => write!(f, "insert {} {} {}", expr.0, expr.1, expr.2),
-            Expr::Inserts        (expr) => write!(f, "inserts {} {} {}", expr.0, expr.1, expr.2),
-            Expr::Set            (expr) => write!(f, "set {} {} {}", expr.0, expr.1, expr.2),
-            Expr::Sets           (expr) => write!(f, "sets {} {} {}", expr.0, expr.1, expr.2),
-            Expr::Len            (expr) => write!(f, "len {}", expr),
-            Expr::FSRead         (expr) => write!(f, "fsread {}", expr),
-            Expr::Lit            (lit)  => write!(f, "{}", lit),
-            Expr::If             (expr) => write!(f, "if {} {} {}", expr.0, expr.1, expr.2),
-            Expr::Range          (expr) => write!(f, "range {} {}", expr.0, expr.1),
-            Expr::Str            (expr) => write!(f, "str {}", expr),
-            Expr::Int            (expr) => write!(f, "int {}", expr),
+            ExprKind::Builtin (builtin, args) => {
+                write!(f, "{}", builtin.keyword())?;
+                for (i, arg,) in args.iter().enumerate() {
+                    // Would otherwise take the arguments after it as its own.
+                    if (i + 1 < args.len() && arg.kind.takes_rest()) { write!(f, " ({})", arg)?; }
+                    else { write!(f, " {}", arg)?; }
+                }
+                Ok(())
+            },
+            ExprKind::Lit     (lit)           => write!(f, "{}", lit)
         }
     }
 }
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Lit {
     Bool(bool),
     Int(i128),
@@ -82,9 +82,40 @@ impl fmt::Display for Lit {
         match (self) {
             Lit::Bool      (v) => if (*v) { write!(f, "true") } else { write!(f, "false") },
             Lit::Int       (v) => write!(f, "{}", v),
-            Lit::Float     (v) => write!(f, "{}", v),
-            Lit::String    (v) => write!(f, "{:?}", v),
+            Lit::Float     (v) => fmt_float(f, *v),
+            Lit::String    (v) => fmt_string(f, v),
             Lit::ExprQueue     => write!(f, "queue")
         }
     }
 }
+
+// Enough digits that parsing the output gives back exactly the same float,
+//  and always with a fractional part so that it doesn't parse as an int.
+fn fmt_float(f : &mut fmt::Formatter<'_>, v : f128) -> fmt::Result {
+    let mut s = v.to_string_fmt("%.36Qg").ok_or(fmt::Error)?;
+    if (s.ends_with("nan")) { return write!(f, "nan"); }
+    if (s.ends_with("inf")) { return write!(f, "{}", s); }
+    if (! s.contains('.')) {
+        let i = s.find('e').unwrap_or(s.len());
+        s.insert_str(i, ".0");
+    }
+    write!(f, "{}", s)
+}
+
+// Escapes exactly what the parser can't take verbatim.
+fn fmt_string(f : &mut fmt::Formatter<'_>, v : &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for ch in v.chars() {
+        match (ch) {
+            '\\'                  => write!(f, "\\\\")?,
+            '"'                   => write!(f, "\\\"")?,
+            '\n'                  => write!(f, "\\n")?,
+            '\r'                  => write!(f, "\\r")?,
+            '\t'                  => write!(f, "\\t")?,
+            '\0'                  => write!(f, "\\0")?,
+            ch if ch.is_control() => write!(f, "\\u{{{:x}}}", ch as u32)?,
+            ch                    => write!(f, "{}", ch)?
+        }
+    }
+    write!(f, "\"")
+}
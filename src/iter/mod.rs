@@ -1,11 +1,5 @@
-mod limit;
-use limit::*;
-
-
 pub trait IteratorExt : Iterator + Sized {
 
-    fn limit(self, count : usize) -> Limit<Self>;
-
     fn next_n_exact(&mut self, count : usize) -> Option<Vec<Self::Item>>;
 
 }
@@ -15,10 +9,6 @@ where
     I : Iterator
 {
 
-    fn limit(self, count : usize) -> Limit<Self> {
-        Limit { iter : self, remaining : count }
-    }
-
     fn next_n_exact(&mut self, count : usize) -> Option<Vec<Self::Item>> {
         let mut out = Vec::with_capacity(count);
         for _ in 0..count {
@@ -2,17 +2,23 @@
 
 
 #![feature(
-    never_type,
-    decl_macro,
-    iter_intersperse
+    never_type
 )]
 
 
+pub mod builtin;
+
 pub mod expr;
 
+pub mod span;
+
 pub mod parser;
 
 pub mod exec;
 
+pub mod debug;
+
+pub mod dap;
+
 
 mod iter;
@@ -1,31 +1,132 @@
-use peg::error::{ ParseError, ExpectedSet };
+use crate::span::{ Span, Origin };
+use core::fmt;
+use std::error::Error;
+use std::io::{ self, IsTerminal, Write };
+use std::env;
+use peg::error::ParseError;
 use peg::str::LineCol;
+use serde_json::json;
 
 
-pub struct ParserError<'l> {
-    line     : &'l str,
-    column   : usize,
-    expected : ExpectedSet
+/// Every line of a script that failed to parse.
+#[derive(Debug, Clone)]
+pub struct ParserError {
+    pub diagnostics : Vec<Diagnostic>
 }
 
-impl<'l> ParserError<'l> {
-    pub(super) fn from_peg(line : &'l str, error : ParseError<LineCol>) -> Self {
-        Self { line, column : error.location.column - 1, expected : error.expected }
+/// A single line that failed to parse.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub origin   : Origin,
+    pub line     : usize, // 1-indexed
+    pub column   : usize, // 1-indexed, in characters
+    pub source   : String,
+    pub expected : Vec<String>
+}
+
+impl Diagnostic {
+    pub(super) fn from_peg(span : Span, source : &str, error : ParseError<LineCol>) -> Self {
+        Self {
+            origin   : span.origin,
+            line     : span.line,
+            column   : span.column,
+            source   : source.to_string(),
+            expected : error.expected.tokens().map(String::from).collect()
+        }
+    }
+
+    fn location(&self) -> String {
+        match (&self.origin) {
+            Origin::Unknown => format!("line {}", self.line),
+            origin          => format!("line {} of {}", self.line, origin)
+        }
+    }
+
+    fn expectation(&self) -> String {
+        match (self.expected.len()) {
+            0   => "Unexpected input".to_string(),
+            1   => format!("Expected {}", self.expected[0]),
+            2.. => format!("Expected one of {}", self.expected.join(", "))
+        }
+    }
+
+    /// Whitespace that lines a caret up with the failing column, keeping tabs
+    ///  from the source line so it still lines up when they are expanded.
+    fn caret_indent(&self) -> String {
+        self.source.chars()
+            .take(self.column - 1)
+            .map(|ch| if (ch == '\t') { '\t' } else { ' ' })
+            .collect()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Failed to parse {}", self.location())?;
+        writeln!(f, "{}", self.source)?;
+        writeln!(f, "{}^", self.caret_indent())?;
+        write!(f, "{}", self.expectation())
+    }
+}
+
+
+/// How a [`ParserError`] is written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Render {
+    Colored,
+    Plain,
+    Json
+}
+
+impl Render {
+    /// Colored, unless `NO_COLOR` is set or stderr is not a terminal.
+    pub fn detect() -> Self {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|v| ! v.is_empty());
+        if (no_color || ! io::stderr().is_terminal()) { Self::Plain }
+        else { Self::Colored }
     }
 }
 
-impl ParserError<'_> {
-    pub fn print_formatted(&self) {
-        println!("\x1b[0m\x1b[97m\x1b[101m\x1b[1m Failed to parse line \x1b[0m");
-        println!("\x1b[96m{}\x1b[0m", self.line);
-        println!("{}\x1b[93m\x1b[1m^\x1b[0m", " ".repeat(self.column));
-        match (self.expected.tokens().count()) {
-            0   => unreachable!(),
-            1   => println!("\x1b[91m\x1b[1mExpected {}\x1b[0m", self.expected.tokens().next().unwrap()),
-            2.. => println!("\x1b[91m\x1b[1mExpected one of {}\x1b[0m", self.expected.tokens()
-                .intersperse(", ")
-                .collect::<String>()
-            )
+
+impl ParserError {
+
+    pub fn render(&self, render : Render) -> String {
+        match (render) {
+            Render::Colored => self.diagnostics.iter().map(|d| {
+                format!(
+                    "\x1b[0m\x1b[97m\x1b[101m\x1b[1m Failed to parse {} \x1b[0m\n\x1b[96m{}\x1b[0m\n{}\x1b[93m\x1b[1m^\x1b[0m\n\x1b[91m\x1b[1m{}\x1b[0m\n",
+                    d.location(), d.source, d.caret_indent(), d.expectation()
+                )
+            }).collect(),
+            Render::Plain   => self.diagnostics.iter().map(|d| format!("{}\n", d)).collect(),
+            Render::Json    => {
+                let errors = self.diagnostics.iter().map(|d| json!({
+                    "origin"   : d.origin.to_string(),
+                    "line"     : d.line,
+                    "column"   : d.column,
+                    "source"   : d.source,
+                    "expected" : d.expected
+                })).collect::<Vec<_>>();
+                format!("{}\n", json!({ "errors" : errors }))
+            }
         }
     }
+
+    /// Writes the rendered diagnostics to stderr.
+    pub fn eprint(&self, render : Render) {
+        let _ = io::stderr().lock().write_all(self.render(render).as_bytes());
+    }
+
 }
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, diagnostic,) in self.diagnostics.iter().enumerate() {
+            if (i != 0) { writeln!(f)?; }
+            write!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParserError { }
@@ -1,4 +1,6 @@
-use crate::expr::{ Expr, Lit };
+use crate::builtin::Builtin;
+use crate::expr::{ Expr, ExprKind, Lit };
+use crate::span::{ Span, Origin };
 use peg;
 use unicode_ident::{ is_xid_start, is_xid_continue };
 use f128::f128;
@@ -15,66 +17,159 @@ impl StringTerminator {
 }
 
 
-pub fn parse<'l>(script : &'l str) -> Result<Vec<Expr>, ParserError<'l>> {
-    sisyphys_parser::script(&script).map_err(|e| ParserError::from_peg(script.lines().nth(e.location.line - 1).unwrap_or(""), e))
+pub fn parse(script : &str) -> Result<Vec<Expr>, ParserError> {
+    parse_with_origin(script, Origin::Unknown)
 }
 
+pub fn parse_with_origin(script : &str, origin : Origin) -> Result<Vec<Expr>, ParserError> {
+    // The byte order mark is invisible, so stripping it up front keeps the
+    //  reported columns of the first line lined up with what editors show.
+    let script = script.strip_prefix('\u{feff}').unwrap_or(script);
+    let spans  = SpanContext::new(script, origin);
+    sisyphys_parser::script(script, &spans).map_err(|e| ParserError { diagnostics : recover(&spans, e) })
+}
+
+/// Collects a diagnostic for every top-level expression that fails to parse,
+///  by resuming after each failure at the next line that isn't indented or
+///  inside a block comment.
+fn recover(spans : &SpanContext<'_>, first_error : peg::error::ParseError<peg::str::LineCol>) -> Vec<Diagnostic> {
+    let script          = spans.script;
+    let mut diagnostics = Vec::new();
+    let mut resume      = 0;
+    let mut error       = first_error;
+    loop {
+        let offset = resume + error.location.offset;
+        let span   = spans.span(offset);
+        let line   = span.line;
+        let source = script.lines().nth(line - 1).unwrap_or("");
+        diagnostics.push(Diagnostic::from_peg(span, source, error));
+
+        let Some(next) = next_top_level_line(script, resume, spans.line_starts[line - 1])
+            else { break; };
+        resume = next;
+        let rest = &script[resume..];
+        match (sisyphys_parser::script(rest, &SpanContext::new(rest, Origin::Unknown))) {
+            Ok(_)  => break,
+            Err(e) => { error = e; }
+        }
+    }
+    diagnostics
+}
+
+
+/// The start of the first line after the one starting at `after` that isn't
+///  indented and doesn't start inside a block comment. Scanning starts at
+///  `from`, which can't be inside a comment or a string.
+fn next_top_level_line(script : &str, from : usize, after : usize) -> Option<usize> {
+    let mut chars      = script[from..].char_indices().map(|(i, ch,)| (from + i, ch,)).peekable();
+    let mut depth      = 0usize;
+    let mut string     = None;
+    let mut line_start = true;
+    while let Some((i, ch,)) = chars.next() {
+        if (line_start && i > after && depth == 0 && ! ch.is_whitespace()) { return Some(i); }
+        line_start = ch == '\n';
+        let next = chars.peek().map(|&(_, ch,)| ch);
+        match (string) {
+            // Strings can't span lines, so a newline ends an unterminated one.
+            Some(terminator) => match (ch, next,) {
+                ('\\', Some(ch)) if (ch != '\n')  => { chars.next(); },
+                ('\n', _)                         => { string = None; },
+                (ch, _) if (ch == terminator)     => { string = None; },
+                _                                 => { }
+            },
+            None             => match (ch, next,) {
+                ('/', Some('*'))                 => { depth += 1; chars.next(); },
+                ('*', Some('/')) if (depth > 0)  => { depth -= 1; chars.next(); },
+                ('/', Some('/')) if (depth == 0) => { while (chars.next_if(|&(_, ch,)| ch != '\n').is_some()) { } },
+                ('"' | '`', _) if (depth == 0)   => { string = Some(ch); },
+                _                                => { }
+            }
+        }
+    }
+    None
+}
+
+
+struct SpanContext<'l> {
+    script      : &'l str,
+    origin      : Origin,
+    line_starts : Vec<usize>
+}
+
+impl<'l> SpanContext<'l> {
+    fn new(script : &'l str, origin : Origin) -> Self {
+        let line_starts = [0].into_iter()
+            .chain(script.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { script, origin, line_starts }
+    }
+
+    fn span(&self, offset : usize) -> Span {
+        let line       = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        Span {
+            origin : self.origin.clone(),
+            line,
+            column : self.script[line_start..offset].chars().count() + 1
+        }
+    }
+}
 
-peg::parser! { grammar sisyphys_parser() for str {
+
+peg::parser! { grammar sisyphys_parser(spans : &SpanContext<'_>) for str {
 
     pub(super) rule script() -> Vec<Expr>
-        = _ e:( e:expr() _ { e } ) ** ( "\n" _ ) { e }
+        = shebang()? ( _ eol() )*
+            _ e:( e:expr() _ { e } ) ** ( eol() ( _ eol() )* _ )
+            ( _ eol() )* _ line_comment()?
+            { e }
 
     rule expr() -> Expr
-        = "print"   __ a:expr_args(1) { destructure_expr_args!( a => v,       ); Expr::Print         (Box::new(v)) }
-        / "+"       __ a:expr_args(2) { destructure_expr_args!( a => l, r,    ); Expr::Add           (Box::new((l, r,))) }
-        / "-"       __ a:expr_args(2) { destructure_expr_args!( a => l, r,    ); Expr::Sub           (Box::new((l, r,))) }
-        / "*"       __ a:expr_args(2) { destructure_expr_args!( a => l, r,    ); Expr::Mul           (Box::new((l, r,))) }
-        / "/"       __ a:expr_args(2) { destructure_expr_args!( a => l, r,    ); Expr::Div           (Box::new((l, r,))) }
-        / "!"       __ a:expr_args(1) { destructure_expr_args!( a => b,       ); Expr::Not           (Box::new((b, ))) }
-        / ">="      __ a:expr_args(2) { destructure_expr_args!( a => l, r,    ); Expr::GreaterEquals (Box::new((l, r,))) }
-        / "<="      __ a:expr_args(2) { destructure_expr_args!( a => l, r,    ); Expr::LessEquals    (Box::new((l, r,))) }
-        / "="       __ a:expr_args(2) { destructure_expr_args!( a => l, r,    ); Expr::Equals        (Box::new((l, r,))) }
-        / ">"       __ a:expr_args(2) { destructure_expr_args!( a => l, r,    ); Expr::Greater       (Box::new((l, r,))) }
-        / "<"       __ a:expr_args(2) { destructure_expr_args!( a => l, r,    ); Expr::Less          (Box::new((l, r,))) }
-        / "gets"    __ a:expr_args(3) { destructure_expr_args!( a => q, s, e  ); Expr::Gets          (Box::new((q, s, e))) }
-        / "get"     __ a:expr_args(2) { destructure_expr_args!( a => q, i,    ); Expr::Get           (Box::new((q, i,))) }
-        / "pushes"  __ a:expr_args(2) { destructure_expr_args!( a => q, l,    ); Expr::Pushes        (Box::new((q, l,))) }
-        / "push"    __ a:expr_args(2) { destructure_expr_args!( a => q, v,    ); Expr::Push          (Box::new((q, v,))) }
-        / "inserts" __ a:expr_args(3) { destructure_expr_args!( a => q, s, l, ); Expr::Inserts       (Box::new((q, s, l,))) }
-        / "insert"  __ a:expr_args(3) { destructure_expr_args!( a => q, i, v, ); Expr::Insert        (Box::new((q, i, v,))) }
-        / "sets"    __ a:expr_args(3) { destructure_expr_args!( a => q, s, l, ); Expr::Sets          (Box::new((q, s, l,))) }
-        / "set"     __ a:expr_args(3) { destructure_expr_args!( a => q, i, v, ); Expr::Set           (Box::new((q, i, v,))) }
-        / "len"     __ a:expr_args(1) { destructure_expr_args!( a => q,       ); Expr::Print         (Box::new(q)) }
-        / "fsread"  __ a:expr_args(1) { destructure_expr_args!( a => f,       ); Expr::FSRead        (Box::new(f)) }
-        / "if"      __ a:expr_args(3) { destructure_expr_args!( a => c, t, f, ); Expr::If            (Box::new((c, t, f))) }
-        / "range"   __ a:expr_args(2) { destructure_expr_args!( a => i0, i1,  ); Expr::Range         (Box::new((i0, i1,))) }
-        / "str"     __ a:expr_args(1) { destructure_expr_args!( a => v,       ); Expr::Str           (Box::new(v)) }
-        / "int"     __ a:expr_args(1) { destructure_expr_args!( a => v,       ); Expr::Int           (Box::new(v)) }
-        / l:lit() { Expr::Lit(l) }
+        = "(" __? e:expr() __? ")" { e }
+        / p:position!() k:expr_kind() { Expr::new(k, spans.span(p)) }
+
+    rule expr_kind() -> ExprKind
+        = b:builtin() a:expr_args(b.arity()) r:expr_rest(b.variadic()) { ExprKind::Builtin(b, [a, r].concat()) }
+        / l:lit() { ExprKind::Lit(l) }
+
+    // Keywords are either a word or a run of operator characters, looked up
+    //  in the builtin table. Comments are never part of an operator.
+    rule builtin() -> Builtin
+        = quiet!{ k:$( ['a'..='z']+ / ( !( "//" / "/*" ) ['+' | '-' | '*' | '/' | '%' | '!' | '=' | '<' | '>'] )+ )
+            {? Builtin::from_keyword(k).ok_or("builtin") } }
+        / expected!("builtin")
 
     rule expr_args(n : usize) -> Vec<Expr>
-        = a:( a:expr() { a } )**<{n}> __ { a }
+        = a:( __ a:expr() { a } )*<{n}> { a }
+
+    // Variadic builtins take every argument that follows.
+    rule expr_rest(variadic : bool) -> Vec<Expr>
+        = a:( __ a:expr() { a } )*<0, {if (variadic) { usize::MAX } else { 0 }}> { a }
 
     rule lit() -> Lit
         = s:lit_string(StringTerminator::NORMAL) { Lit::String(s) }
         / b:lit_bool() { Lit::Bool(b) }
-        / i:lit_int() { Lit::Int(i) }
         / f:lit_float() { Lit::Float(f) }
+        / i:lit_int() { Lit::Int(i) }
         / "queue" { Lit::ExprQueue }
 
     rule lit_bool() -> bool
         = "true" { true } 
         / "false" { false }
 
+    // A sign has to be attached to the literal, as "- " is subtraction.
     rule lit_float() -> f128
-        = quiet!{ f:$(lit_int() "." lit_int()) {? f128::parse(f).or(Err("bad float")) } }
+        = quiet!{ f:$("-"? digits() "." digits() ( ['e' | 'E'] ['+' | '-']? digits() )?) {? f128::parse(f).or(Err("bad float")) } }
+        / quiet!{ f:$("-"? ( "inf" / "nan" )) {? f128::parse(f).or(Err("bad float")) } }
         / expected!("float")
 
     rule lit_int() -> i128
-        = quiet!{ s:$(['0'..='9']+) {? s.parse().or(Err("bad int")) } }
+        = quiet!{ s:$("-"? digits()) {? s.parse().or(Err("bad int")) } }
         / expected!("integer")
 
+    rule digits() -> ()
+        = ['0'..='9']+ { () }
+
     rule ident() -> String
         = quiet!{
             start_char:[c if is_xid_start(c)] continue_chars:([c if is_xid_continue(c)])* {
@@ -120,16 +215,28 @@ peg::parser! { grammar sisyphys_parser() for str {
         / expected!("valid escape sequence")
 
 
+    rule shebang() -> ()
+        = quiet!{ "#!" ( !newline() [_] )* { () } }
+
+    rule line_comment() -> ()
+        = quiet!{ "//" ( !newline() [_] )* { () } }
+    rule block_comment() -> ()
+        = quiet!{ "/*" ( block_comment() / !"*/" [_] )* "*/" { () } }
+
+    rule newline() -> ()
+        = quiet!{ ( "\r\n" / "\n" ) { () } }
+        / expected!("newline")
+    // The end of a line, along with any trailing whitespace and comment.
+    rule eol() -> ()
+        = _ line_comment()? newline()
+
+    // Separates the arguments of an expression. Arguments may continue on
+    //  the following lines, as long as those lines are indented, with blank
+    //  or comment-only lines in between.
     rule __() -> ()
-        = quiet!{ (" " / "\t")+ { () } }
+        = quiet!{ ( (" " / "\t")+ / block_comment() / ( line_comment()? newline() eol()* (" " / "\t")+ ) )+ { () } }
     rule _() -> ()
-        = quiet!{ (" " / "\t")* { () } }
+        = quiet!{ ( (" " / "\t")+ / block_comment() )* { () } }
 
 } }
 
-
-
-macro destructure_expr_args( $a:expr => $( $out:pat ),+ $(,)? ) {
-    let mut a = ($a).into_iter();
-    $( let $out = a.next().unwrap(); )+
-}
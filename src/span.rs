@@ -0,0 +1,71 @@
+use core::fmt;
+use std::sync::Arc;
+
+
+/// Where an expression came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub origin : Origin,
+    pub line   : usize, // 1-indexed
+    pub column : usize  // 1-indexed, in characters
+}
+
+impl Span {
+    pub fn unknown() -> Self {
+        Self { origin : Origin::Unknown, line : 1, column : 1 }
+    }
+
+    /// Follows pushed origins back to the span that was written in a source,
+    ///  if any.
+    pub fn root(&self) -> &Span {
+        match (&self.origin) {
+            Origin::Pushed { by, .. } => by,
+            _                         => self
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.origin) {
+            Origin::Unknown                => write!(f, "{}:{}", self.line, self.column),
+            Origin::File      (name)       => write!(f, "{}:{}:{}", name, self.line, self.column),
+            origin @ Origin::Pushed { .. } => write!(f, "{}:{} ({})", self.line, self.column, origin)
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Unknown,
+    /// Parsed from the named file.
+    File(Arc<str>),
+    /// Parsed at runtime from a string that an expression added to the queue.
+    ///  `by` is always a root span, so chains of pushes don't nest.
+    Pushed { by : Arc<Span>, tick : u64 }
+}
+
+impl Origin {
+    pub fn file(name : impl Into<Arc<str>>) -> Self {
+        Self::File(name.into())
+    }
+
+    /// The origin for expressions added to the queue by `by` during `tick`.
+    pub fn pushed(by : &Span, tick : u64) -> Self {
+        Self::Pushed { by : Arc::new(by.root().clone()), tick }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self) {
+            Origin::Unknown            => write!(f, "unknown"),
+            Origin::File   (name)      => write!(f, "{}", name),
+            Origin::Pushed { by, tick } => match (&by.origin) {
+                Origin::File(name) => write!(f, "pushed by {}:{} at tick {}", name, by.line, tick),
+                _                  => write!(f, "pushed by line {} at tick {}", by.line, tick)
+            }
+        }
+    }
+}
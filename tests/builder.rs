@@ -0,0 +1,80 @@
+use sisyphus::builtin::Group;
+use sisyphus::exec::{ Capture, Executor, Limits, Outcome, Overflow, Reader, Value, ErrorKind };
+use sisyphus::exec::vfs::{ Access, Memory };
+
+
+const MAX : &str = "170141183460469231731687303715884105727";
+const MIN : &str = "-170141183460469231731687303715884105728";
+
+fn outcome(e : &mut Executor, source : &str) -> Outcome {
+    e.run_source(source).unwrap().outcome
+}
+
+fn failure(e : &mut Executor, source : &str) -> ErrorKind {
+    let Outcome::Failed(err) = outcome(e, source)
+        else { panic!("expected {} to fail", source); };
+    err.kind
+}
+
+
+#[test]
+fn builds_a_configured_executor() {
+    let output = Capture::new();
+    let files  = Memory::new();
+    let mut e = Executor::builder()
+        .output(output.clone())
+        .input(Reader::new("line\n".as_bytes()))
+        .file_system(files.clone())
+        .file_access(Access::ReadOnly)
+        .max_ticks(3)
+        .stop_on_error()
+        .native("answer", 0, |_, _| Value::Int(42))
+        .build();
+    assert_eq!(e.limits(), Limits::new(3).with_stop_on_error());
+    assert_eq!(e.file_access(), Access::ReadOnly);
+
+    let report = e.run_source("print readln\nprint (call \"answer\")\nprint 3\nprint 4").unwrap();
+    assert_eq!((report.outcome, report.ticks,), (Outcome::BudgetExhausted, 3,));
+    assert_eq!(output.take(), "line\n42\n3\n");
+    assert_eq!(failure(&mut e, "fswrite \"a\" \"b\""), ErrorKind::Io);
+}
+
+#[test]
+fn run_source_queues_nothing_that_fails_to_parse() {
+    let mut e = Executor::builder().output(Capture::new()).limits(Limits::new(10).with_queue(2)).build();
+    assert!(e.run_source("print 1\nprint (").is_err());
+    assert_eq!(e.len_exprs(), 1);
+    assert_eq!(outcome(&mut e, "print 1\nprint 2\nprint 3"), Outcome::QueueLimitExceeded);
+}
+
+#[test]
+fn overflow_follows_the_policy() {
+    let mut e = Executor::builder().output(Capture::new()).stop_on_error().build();
+    for source in [format!("+ {} 1", MAX), format!("- {} 1", MIN), format!("* {} 2", MAX), format!("/ {} -1", MIN), format!("! {}", MIN), format!("+ true {}", MAX)] {
+        assert_eq!(failure(&mut e, &source), ErrorKind::Overflow, "{}", source);
+    }
+    assert_eq!(outcome(&mut e, &format!("% {} -1", MIN)), Outcome::Completed);
+
+    for (overflow, added, negated,) in [(Overflow::Wrap, MIN, MIN), (Overflow::Saturate, MAX, MAX)] {
+        let output = Capture::new();
+        let mut e = Executor::builder().output(output.clone()).overflow(overflow).build();
+        assert_eq!(outcome(&mut e, &format!("print (+ {} 1)\nprint (! {})", MAX, MIN)), Outcome::Completed);
+        assert_eq!(output.contents(), format!("{}\n{}\n", added, negated));
+    }
+}
+
+#[test]
+fn disabled_groups_fail_to_run() {
+    let output = Capture::new();
+    let mut e = Executor::builder().output(output.clone()).file_system(Memory::new()).disable(Group::Fs).stop_on_error().build();
+    assert!(! e.group_enabled(Group::Fs));
+    assert_eq!(failure(&mut e, "fswrite \"a\" \"b\""), ErrorKind::Disabled);
+    assert_eq!(outcome(&mut e, "print 1"), Outcome::Completed);
+
+    let mut e = Executor::builder().output(output.clone()).groups(&[Group::Fs]).stop_on_error().build();
+    assert_eq!(failure(&mut e, "print 2"), ErrorKind::Disabled);
+    assert_eq!(failure(&mut e, "exit 0"), ErrorKind::Disabled);
+    e.set_group_enabled(Group::Io, true);
+    assert_eq!(outcome(&mut e, "print 3"), Outcome::Completed);
+    assert_eq!(output.contents(), "1\n3\n");
+}
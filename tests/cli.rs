@@ -0,0 +1,254 @@
+use std::io::Write;
+use std::process::{ Command, Output, Stdio };
+
+
+fn sisyphus(args : &[&str], stdin : &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sisyphus"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn().unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output : &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output : &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+
+#[test]
+fn runs_a_script_from_a_file() {
+    let out = sisyphus(&["run", "samples/sqrt.push"], "");
+    assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+    assert!(! out.stdout.is_empty());
+}
+
+#[test]
+fn runs_a_script_from_stdin_without_a_command() {
+    let out = sisyphus(&["-"], "print + 1 2\nprint \"done\"\n");
+    assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+    assert_eq!(stdout(&out), "3\ndone\n");
+}
+
+#[test]
+fn check_only_parses() {
+    let out = sisyphus(&["check", "-"], "print 1\n");
+    assert_eq!(out.status.code(), Some(0));
+    assert!(out.stdout.is_empty());
+
+    let out = sisyphus(&["check", "-"], "print 1\nprint (\n");
+    assert_eq!(out.status.code(), Some(3));
+    assert!(stderr(&out).contains("Failed to parse line 2 of <stdin>"), "{}", stderr(&out));
+}
+
+#[test]
+fn fmt_prints_the_canonical_form() {
+    let out = sisyphus(&["fmt", "-"], "print (+ 1   2)\n\n  (len queue)\nprint \"// not a comment\"\n");
+    assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+    assert_eq!(stdout(&out), "print + 1 2\nlen queue\nprint \"// not a comment\"\n");
+}
+
+#[test]
+fn fmt_refuses_scripts_it_would_drop_parts_of() {
+    for (script, lost,) in [
+        ("#!/usr/bin/env sisyphus\nprint 1\n", "a shebang line"),
+        ("// comment\nprint 1\n",               "comments"),
+        ("print 1 /* c */\n",                    "comments"),
+        ("print (+ 1\n    2)\n",                "expressions continued on other lines")
+    ] {
+        let out = sisyphus(&["fmt", "-"], script);
+        assert_eq!(out.status.code(), Some(3), "{:?}", script);
+        assert!(out.stdout.is_empty());
+        assert!(stderr(&out).contains(lost), "{}", stderr(&out));
+    }
+}
+
+#[test]
+fn runtime_errors_are_ignored_by_default() {
+    let out = sisyphus(&["-"], "print 1\n+ 1 (/ 1 0)\nprint 2\n");
+    assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+    assert_eq!(stdout(&out), "1\n2\n");
+}
+
+#[test]
+fn exit_codes_out_of_range_are_errors() {
+    for code in ["256", "-1"] {
+        let out = sisyphus(&["-", "--stop-on-error"], &format!("print 1\nexit {}\nprint 2\n", code));
+        assert_eq!(out.status.code(), Some(4), "exit {}", code);
+        assert!(stderr(&out).contains("out_of_range: exit code"), "{}", stderr(&out));
+
+        let out = sisyphus(&["-"], &format!("exit {}\nexit 7\n", code));
+        assert_eq!(out.status.code(), Some(7), "exit {}", code);
+    }
+    assert_eq!(sisyphus(&["-"], "exit 255\n").status.code(), Some(255));
+}
+
+#[test]
+fn runtime_errors_stop_the_run() {
+    let out = sisyphus(&["-", "--stop-on-error"], "print 1\n+ 1 (/ 1 0)\nprint 2\n");
+    assert_eq!(out.status.code(), Some(4));
+    assert_eq!(stdout(&out), "1\n");
+    assert!(stderr(&out).contains("<stdin>:2:6: division_by_zero"), "{}", stderr(&out));
+}
+
+#[test]
+fn tick_limit() {
+    let script = "push queue \"push queue (get queue 0)\"\n";
+    let out = sisyphus(&["-", "--max-ticks", "50"], script);
+    assert_eq!(out.status.code(), Some(5));
+    assert!(stderr(&out).contains("Stopped after 50 ticks"), "{}", stderr(&out));
+}
+
+#[test]
+fn queue_limit() {
+    let script = "pushes queue (* (push (range 0 0) \"pushes queue (gets queue 0 2)\") 2)\n";
+    let out = sisyphus(&["-", "--max-queue", "100"], script);
+    assert_eq!(out.status.code(), Some(5));
+    assert!(stderr(&out).contains("more than 100 expressions are queued"), "{}", stderr(&out));
+}
+
+#[test]
+fn trace_prints_each_expression() {
+    let out = sisyphus(&["run", "-", "--trace"], "print 1\nprint 2\n");
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(stderr(&out), "[1] <stdin>:1:1: print 1\n[2] <stdin>:2:1: print 2\n");
+}
+
+#[test]
+fn bad_arguments() {
+    assert_eq!(sisyphus(&[], "").status.code(), Some(2));
+    assert_eq!(sisyphus(&["-", "--max-ticks"], "").status.code(), Some(2));
+    assert_eq!(sisyphus(&["-", "--max-ticks", "many"], "").status.code(), Some(2));
+    assert_eq!(sisyphus(&["-", "--bogus"], "").status.code(), Some(2));
+    assert_eq!(sisyphus(&["check", "-", "--trace"], "").status.code(), Some(2));
+    assert_eq!(sisyphus(&["dap", "-"], "").status.code(), Some(2));
+    assert_eq!(sisyphus(&["dap", "--trace"], "").status.code(), Some(2));
+    assert_eq!(sisyphus(&["--help"], "").status.code(), Some(0));
+}
+
+#[test]
+fn unreadable_script() {
+    assert_eq!(sisyphus(&["samples/does_not_exist.push"], "").status.code(), Some(1));
+}
+
+#[test]
+fn exit_sets_the_exit_code() {
+    let out = sisyphus(&["-"], "print 1\nexit 7\nprint 2\n");
+    assert_eq!(out.status.code(), Some(7));
+    assert_eq!(stdout(&out), "1\n");
+}
+
+#[test]
+fn time_limit() {
+    let script = "push queue \"push queue (get queue 0)\"\n";
+    let out = sisyphus(&["-", "--max-time", "0.05"], script);
+    assert_eq!(out.status.code(), Some(5));
+    assert!(stderr(&out).contains("Stopped after"), "{}", stderr(&out));
+}
+
+#[test]
+fn reads_stdin_as_a_filter() {
+    let dir    = tempfile::TempDir::new().unwrap();
+    let script = dir.path().join("upper.push");
+    std::fs::write(&script, "print + \"1: \" readln\nprint + \"2: \" readln\n").unwrap();
+    let out = sisyphus(&[script.to_str().unwrap()], "a\nb\nc\n");
+    assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+    assert_eq!(stdout(&out), "1: a\n2: b\n");
+}
+
+#[test]
+fn stdin_queue() {
+    let dir    = tempfile::TempDir::new().unwrap();
+    let script = dir.path().join("start.push");
+    std::fs::write(&script, "print \"start\"\n").unwrap();
+    let out = sisyphus(&[script.to_str().unwrap(), "--stdin-queue"], "print 1\nprint + 1 1\n");
+    assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+    assert_eq!(stdout(&out), "start\n1\n2\n");
+
+    assert_eq!(sisyphus(&["-", "--stdin-queue"], "").status.code(), Some(2));
+}
+
+#[test]
+fn save_and_resume() {
+    let dir      = tempfile::TempDir::new().unwrap();
+    let snapshot = dir.path().join("state");
+    let snapshot = snapshot.to_str().unwrap();
+
+    let whole = sisyphus(&["samples/sqrt.push"], "");
+    let first = sisyphus(&["samples/sqrt.push", "--max-ticks", "20", "--save", snapshot], "");
+    assert_eq!(first.status.code(), Some(5));
+    let rest = sisyphus(&["--resume", snapshot], "");
+    assert_eq!(rest.status.code(), Some(0), "{}", stderr(&rest));
+    assert_eq!(stdout(&first) + &stdout(&rest), stdout(&whole));
+
+    assert_eq!(sisyphus(&["samples/sqrt.push", "--resume", snapshot], "").status.code(), Some(2));
+    assert_eq!(sisyphus(&["--resume", "samples/sqrt.push"], "").status.code(), Some(1));
+}
+
+#[test]
+fn save_refuses_channels_and_spawned_scripts() {
+    let dir      = tempfile::TempDir::new().unwrap();
+    let snapshot = dir.path().join("state");
+    for script in ["spawn \"print 2\"\nprint 1\nprint 3\n", "send (channel \"c\") 1\nprint 1\nprint 2\n"] {
+        let out = sisyphus(&["-", "--max-ticks", "2", "--save", snapshot.to_str().unwrap()], script);
+        assert_eq!(out.status.code(), Some(1), "{}", script);
+        assert!(stderr(&out).contains("can't hold channels or spawned scripts"), "{}", stderr(&out));
+        assert!(! snapshot.exists());
+    }
+}
+
+#[test]
+fn record_writes_a_trace() {
+    let dir    = tempfile::TempDir::new().unwrap();
+    let record = dir.path().join("trace");
+    let out = sisyphus(&["-", "--record", record.to_str().unwrap()], "print 1\npush queue \"print 2\"\n");
+    assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+    let trace = std::fs::read_to_string(&record).unwrap();
+    assert!(trace.starts_with("sisyphus-trace 1\nreset 0\n"), "{}", trace);
+    assert!(trace.contains("tick 3 print 2\nresult \"2\"\n"), "{}", trace);
+
+    assert_eq!(sisyphus(&["check", "-", "--record", record.to_str().unwrap()], "").status.code(), Some(2));
+}
+
+#[test]
+fn debug_reads_commands_from_stdin() {
+    let dir    = tempfile::TempDir::new().unwrap();
+    let script = dir.path().join("debug.push");
+    std::fs::write(&script, "print 1\nprint 2\nprint 3\n").unwrap();
+    let out = sisyphus(&["debug", script.to_str().unwrap()], "break tick 3\nstep\ncontinue\nqueue 1 1\neval len queue\ncontinue\nquit\n");
+    assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+    let out = stdout(&out);
+    assert!(out.contains("[1] print 1 => 1\n"), "{}", out);
+    assert!(out.contains("2\nBreakpoint 0: tick 3\n"), "{}", out);
+    assert!(out.contains("   1: print 3\n"), "{}", out);
+    assert!(out.contains("3\nThe queue is empty\n"), "{}", out);
+
+    assert_eq!(sisyphus(&["debug", "-"], "").status.code(), Some(2));
+}
+
+#[test]
+fn dap_serves_stdin() {
+    let body = r#"{"seq":1,"type":"request","command":"initialize","arguments":{}}"#;
+    let out = sisyphus(&["dap"], &format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+    assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+    assert!(stdout(&out).starts_with("Content-Length: "), "{}", stdout(&out));
+    assert!(stdout(&out).contains(r#""success":true"#), "{}", stdout(&out));
+}
+
+#[test]
+fn profile() {
+    let dir    = tempfile::TempDir::new().unwrap();
+    let stacks = dir.path().join("stacks");
+    let out = sisyphus(&["samples/sqrt.push", "--profile", "--stacks", stacks.to_str().unwrap()], "");
+    assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+    assert!(stderr(&out).contains("expression"), "{}", stderr(&out));
+    let stacks = std::fs::read_to_string(&stacks).unwrap();
+    assert!(stacks.lines().all(|line| line.starts_with("samples/sqrt.push:")), "{}", stacks);
+}
@@ -0,0 +1,20 @@
+//! Helpers shared by the tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use sisyphus::exec::{ Execute, Executor, Value, ErrorKind };
+use sisyphus::parser;
+
+
+/// Executes `script`, which must be a single expression, without queueing it.
+pub fn eval(e : &mut Executor, script : &str) -> Value {
+    let exprs = parser::parse(script).unwrap();
+    assert_eq!(exprs.len(), 1);
+    exprs[0].execute(e)
+}
+
+/// The kind of error `script` evaluates to.
+pub fn error(e : &mut Executor, script : &str) -> ErrorKind {
+    let Value::Error(err) = eval(e, script)
+        else { panic!("expected {} to fail", script); };
+    err.kind
+}
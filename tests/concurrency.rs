@@ -0,0 +1,142 @@
+use sisyphus::exec::{ Capture, Executor, Limits, Observer, Outcome, ScheduleReport, Scheduler, Trace, TraceEvent, ErrorKind };
+use sisyphus::expr::Expr;
+use sisyphus::parser;
+use std::fs;
+use std::process::Command;
+use std::sync::{ Arc, Mutex };
+
+
+const DOUBLER : &str = "spawn (* (push (range 0 0) \"send (channel \\\"out\\\") (* 2 (recv (channel \\\"in\\\")))\") 3)";
+// Sends 5 on its second tick, after the main executor has parked.
+const SENDER  : &str = "spawn (push (push (range 0 0) \"print 0\") \"send (channel \\\"c\\\") 5\")";
+
+fn run(script : &str) -> (ScheduleReport, String, Vec<(usize, u64,)>,) {
+    let output = Capture::new();
+    let mut main = Executor::new().with_output(output.clone());
+    main.push_exprs(parser::parse(script).unwrap());
+    let spawned = output.clone();
+    let mut scheduler = Scheduler::new(main).with_spawner(move || Executor::new().with_output(spawned.clone()));
+    let mut turns = Vec::new();
+    let report = scheduler.run_tracing(Limits::new(1000).with_stop_on_error(), |id, tick, _| turns.push((id, tick,)));
+    (report, output.contents(), turns,)
+}
+
+
+#[test]
+fn spawned_executors_pass_values_through_channels() {
+    let script = format!("{}\nsend (send (send (channel \"in\") 1) 2) 3\nprint (recv (channel \"out\"))\nprint (recv (channel \"out\"))\nprint (recv (channel \"out\"))", DOUBLER);
+    let (report, output, turns,) = run(&script);
+    assert_eq!(report.outcome, Outcome::Completed);
+    assert_eq!(output, "2\n4\n6\n");
+    // Executors alternate, and a parked expression is tried again on its next
+    //  turn, with the same tick number.
+    assert_eq!(turns, [(0, 1,), (1, 1,), (0, 2,), (1, 1,), (0, 3,), (1, 2,), (0, 4,), (1, 3,), (0, 5,)]);
+    assert_eq!(report.ticks, 8);
+}
+
+#[test]
+fn scheduling_is_reproducible() {
+    let script = "spawn \"print 10\"\nspawn (push (push (range 0 0) \"print 20\") \"print 21\")\nprint 1\nprint 2\nprint 3";
+    let (report, output, turns,) = run(script);
+    assert_eq!(output, "10\n20\n1\n21\n2\n3\n");
+    for _ in 0..10 {
+        let again = run(script);
+        assert_eq!((again.0.outcome, again.0.ticks, again.1, again.2,), (report.outcome.clone(), report.ticks, output.clone(), turns.clone(),));
+    }
+}
+
+#[test]
+fn receiving_from_an_empty_channel_parks_the_executor() {
+    // Only the main executor is left, and nothing will ever send to it.
+    let (report, output, _,) = run("print (+ \"before \" (recv (channel \"never\")))\nprint 1");
+    assert_eq!((report.outcome, report.executor, output.as_str(),), (Outcome::Blocked, 0, ""));
+
+    // Nothing before `recv` happens again once the executor is unparked.
+    let (report, output, _,) = run(&format!("{}\n+ (print 1) (recv (channel \"c\"))\nprint 2", SENDER));
+    assert_eq!((report.outcome, output.as_str(),), (Outcome::Completed, "0\n1\n2\n"));
+
+    let mut e = Executor::new();
+    e.push_exprs(parser::parse("recv (channel \"c\")\nprint 1").unwrap());
+    let report = e.run(Limits::new(10));
+    assert_eq!((report.outcome, report.ticks,), (Outcome::Blocked, 0,));
+    assert!(e.blocked());
+    assert_eq!((e.len_exprs(), e.ticks(),), (3, 0,));
+}
+
+#[test]
+fn parked_expressions_pick_up_at_the_recv() {
+    let (report, output, turns,) = run(&format!("{}\nprint (recv (channel \"c\"))", SENDER));
+    assert_eq!((report.outcome, output.as_str(),), (Outcome::Completed, "0\n5\n"));
+    assert_eq!(turns, [(0, 1,), (1, 1,), (0, 2,), (1, 2,), (0, 2,)]);
+    assert_eq!(report.ticks, 4);
+
+    // Whatever finished keeps its value, so the queue is only pushed to once.
+    let (_, output, _,) = run(&format!("{}\nprint (+ (len (push queue \"print 3\")) (recv (channel \"c\")))", SENDER));
+    assert_eq!(output, "0\n7\n3\n");
+
+    // Conditions aren't evaluated again, and the same branch is taken.
+    let (_, output, _,) = run(&format!("{}\nprint (if (print true) (recv (channel \"c\")) 0)", SENDER));
+    assert_eq!(output, "0\ntrue\n5\n");
+    let (_, output, _,) = run(&format!("{}\nprint (if (= 5 (recv (channel \"c\"))) \"five\" \"other\")", SENDER));
+    assert_eq!(output, "0\nfive\n");
+}
+
+// Records the ticks and inserts it sees.
+struct Log(Arc<Mutex<Vec<String>>>);
+
+impl Observer for Log {
+    fn before_tick(&mut self, tick : u64, expr : &Expr) {
+        self.0.lock().unwrap().push(format!("before {} {}", tick, expr));
+    }
+    fn on_insert(&mut self, index : usize, expr : &Expr) {
+        self.0.lock().unwrap().push(format!("insert {} {}", index, expr));
+    }
+}
+
+#[test]
+fn parked_expressions_go_back_to_index_1() {
+    let recorded = Capture::new();
+    let events   = Arc::new(Mutex::new(Vec::new()));
+    let mut main = Executor::new().with_output(Capture::new()).with_observer(Log(events.clone()));
+    main.start_trace(recorded.clone());
+    main.push_exprs(parser::parse(&format!("{}\nprint (recv (channel \"c\"))", SENDER)).unwrap());
+    let mut scheduler = Scheduler::new(main).with_spawner(|| Executor::new().with_output(Capture::new()));
+    assert_eq!(scheduler.run(Limits::new(100)).outcome, Outcome::Completed);
+    scheduler.main_mut().stop_trace().unwrap();
+
+    let spawn  = parser::parse(SENDER).unwrap().remove(0);
+    let parked = parser::parse("print (recv (channel \"c\"))").unwrap().remove(0);
+    assert_eq!(*events.lock().unwrap(), [format!("before 1 {}", spawn), format!("before 2 {}", parked), format!("insert 1 {}", parked), format!("before 2 {}", parked)]);
+    let trace = Trace::read(recorded.contents().as_bytes()).unwrap();
+    assert!(trace.events.contains(&TraceEvent::Requeue(parked)));
+    assert_eq!(trace.ticks().map(|(tick, _, _,)| tick).collect::<Vec<_>>(), [1, 2, 2]);
+    assert_eq!(trace.queue_at(2).map(|queue| queue.len()), Some(1));
+}
+
+#[test]
+fn spawned_executors_fail_and_halt_on_their_own() {
+    let (report, output, _,) = run("spawn \"exit 3\"\nprint 1\nprint 2");
+    assert_eq!((report.outcome, output.as_str(),), (Outcome::Completed, "1\n2\n"));
+
+    let (report, _, _,) = run("spawn \"+ 1 queue\"\nprint 1\nprint 2");
+    assert!(matches!(report.outcome, Outcome::Failed(err) if (err.kind == ErrorKind::TypeMismatch)));
+    assert_eq!(report.executor, 1);
+
+    let (report, _, _,) = run("spawn 1");
+    assert!(matches!(report.outcome, Outcome::Failed(err) if (err.kind == ErrorKind::TypeMismatch)));
+    let (report, _, _,) = run("send 1 2");
+    assert!(matches!(report.outcome, Outcome::Failed(err) if (err.kind == ErrorKind::TypeMismatch)));
+}
+
+#[test]
+fn deadlocks_from_the_command_line() {
+    let dir  = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("deadlock.push");
+    fs::write(&path, "spawn \"recv (channel \\\"a\\\")\"\nprint 1\nrecv (channel \"b\")\nprint 2\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_sisyphus")).arg(&path).arg("--trace").output().unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[1:1] "), "{}", stderr);
+    assert!(stderr.contains("every script is waiting to receive from an empty channel"), "{}", stderr);
+}
@@ -0,0 +1,93 @@
+use sisyphus::exec::{ Capture, Executor, Limits, Outcome, Reader, Value };
+use sisyphus::parser;
+use std::fs;
+use std::process::Command;
+use std::sync::atomic::{ AtomicU64, Ordering };
+
+
+fn executor(script : &str) -> Executor {
+    let mut e = Executor::new().with_output(Capture::new());
+    e.push_exprs(parser::parse(script).unwrap());
+    e
+}
+
+fn state(e : &Executor) -> Vec<String> {
+    e.get_exprs(0, e.len_exprs()).unwrap().iter().map(|expr| expr.to_string()).collect()
+}
+
+/// Runs `script` with cycle detection, and checks the cycle it finds against
+///  the states of a run that steps one tick at a time.
+fn check_cycle(script : &str, length : u64) {
+    let mut e = executor(script);
+    let report = e.run(Limits::new(10_000).with_cycle_detection());
+    let Outcome::CycleDetected(cycle) = report.outcome
+        else { panic!("{:?}", report.outcome); };
+    assert_eq!(cycle.length, length, "{}", script);
+    assert_eq!(cycle.repeat - cycle.first, cycle.length);
+    assert_eq!(report.ticks, cycle.repeat);
+    assert_eq!(e.ticks(), cycle.repeat);
+
+    let mut e      = executor(script);
+    let mut states = vec![state(&e)];
+    while (e.ticks() < cycle.repeat) {
+        assert!(e.tick());
+        states.push(state(&e));
+    }
+    let first = cycle.first as usize;
+    assert_eq!(states[first], states[first + length as usize]);
+    // Ticks after the first one don't count the placeholder executed last.
+    for period in 1..length as usize {
+        assert!((first.max(1)..states.len() - period).any(|tick| states[tick] != states[tick + period]));
+    }
+}
+
+
+#[test]
+fn finds_cycles() {
+    check_cycle("push queue (get queue 0)", 1);
+    check_cycle("print (push queue (get queue 0))\npush queue (get queue 0)", 2);
+    // Each line moves itself behind the next one.
+    check_cycle("insert queue 2 (get queue 0)\nprint (insert queue 2 (get queue 0))\nlen (insert queue 2 (get queue 0))", 2);
+    check_cycle("push (set queue 1 (get queue 0)) (get queue 0)\nprint 1", 1);
+    check_cycle("print 1\ninsert queue 0 0", 1);
+    let rotating = (0..50).map(|k| format!("+ {} (len (push queue (get queue 0)))", k)).collect::<Vec<_>>();
+    check_cycle(&rotating.join("\n"), 50);
+}
+
+#[test]
+fn leaves_other_scripts_alone() {
+    let source = fs::read_to_string("samples/sqrt.push").unwrap();
+    let mut e = executor(&source);
+    assert_eq!(e.run(Limits::new(100_000).with_cycle_detection()).outcome, Outcome::Completed);
+
+    let mut e = executor("pushes queue (* (push (range 0 0) (get queue 0)) 2)");
+    assert_eq!(e.run(Limits::new(200).with_cycle_detection()).outcome, Outcome::BudgetExhausted);
+
+    let mut e = executor("push queue (get queue 0)");
+    assert_eq!(e.run(Limits::new(200)).outcome, Outcome::BudgetExhausted);
+
+    // The same queue, with different input left to read.
+    let mut e = executor("print (+ readln (len (push queue (get queue 0))))")
+        .with_input(Reader::new("a\na\na\na\n".as_bytes()));
+    let report = e.run(Limits::new(4).with_cycle_detection());
+    assert_eq!(report.outcome, Outcome::BudgetExhausted);
+
+    // The same queue, with a native function that counts its calls.
+    let calls = AtomicU64::new(0);
+    let mut e = executor("print (+ (call \"count\") (len (push queue (get queue 0))))")
+        .with_native("count", 0, move |_, _| Value::Int(calls.fetch_add(1, Ordering::Relaxed) as i128));
+    let report = e.run(Limits::new(200).with_cycle_detection());
+    assert_eq!(report.outcome, Outcome::BudgetExhausted);
+}
+
+#[test]
+fn detection_from_the_command_line() {
+    let dir  = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("loop.push");
+    fs::write(&path, "print 1\npush queue (get queue 0)\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_sisyphus")).arg(&path).arg("--detect-cycles").output().unwrap();
+    assert_eq!(output.status.code(), Some(5));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("after tick 3 came back after tick 4, and would repeat every 1 ticks"), "{}", stderr);
+}
@@ -0,0 +1,148 @@
+use sisyphus::dap::{ self, Server };
+use serde_json::{ json, Value as Json };
+use std::fs;
+use tempfile::TempDir;
+
+
+fn script(dir : &TempDir, source : &str) -> String {
+    let path = dir.path().join("script.push");
+    fs::write(&path, source).unwrap();
+    fs::canonicalize(path).unwrap().to_str().unwrap().to_string()
+}
+
+/// Sends every request, numbered from 1, and returns everything the server
+///  sent back.
+fn session(requests : &[(&str, Json)]) -> Vec<Json> {
+    let mut input = Vec::new();
+    for (i, (command, arguments,),) in requests.iter().enumerate() {
+        dap::write_message(&mut input, &json!({ "seq" : i + 1, "type" : "request", "command" : command, "arguments" : arguments })).unwrap();
+    }
+    let mut server = Server::new(Vec::new());
+    server.serve(input.as_slice()).unwrap();
+    let output = server.into_writer();
+    let mut reader = output.as_slice();
+    let mut messages = Vec::new();
+    while let Some(message) = dap::read_message(&mut reader).unwrap() { messages.push(message); }
+    messages
+}
+
+fn response(messages : &[Json], request_seq : usize) -> &Json {
+    messages.iter().find(|message| message["type"] == "response" && message["request_seq"] == request_seq).unwrap()
+}
+
+/// Every event, in order, as `(event, body)`.
+fn events(messages : &[Json]) -> Vec<(&str, &Json,)> {
+    messages.iter().filter(|message| message["type"] == "event")
+        .map(|message| (message["event"].as_str().unwrap(), &message["body"],))
+        .collect()
+}
+
+
+#[test]
+fn stops_on_line_breakpoints() {
+    let dir     = TempDir::new().unwrap();
+    let program = script(&dir, "push queue \"print 4\"\nprint 1\nprint 2\n\nprint 3\n");
+    let messages = session(&[
+        ("initialize",        json!({ "adapterID" : "sisyphus" })),
+        ("launch",            json!({ "program" : program })),
+        ("setBreakpoints",    json!({ "source" : { "path" : program }, "breakpoints" : [{ "line" : 1 }, { "line" : 3 }, { "line" : 4 }] })),
+        ("configurationDone", json!({})),
+        ("continue",          json!({ "threadId" : 1 })),
+        ("threads",           json!({})),
+        ("stackTrace",        json!({ "threadId" : 1 })),
+        ("scopes",            json!({ "frameId" : 1 })),
+        ("variables",         json!({ "variablesReference" : 1 })),
+        ("evaluate",          json!({ "expression" : "len queue", "frameId" : 1 })),
+        ("next",              json!({ "threadId" : 1 })),
+        ("continue",          json!({ "threadId" : 1 })),
+        ("disconnect",        json!({}))
+    ]);
+
+    for seq in 1..=13 { assert_eq!(response(&messages, seq)["success"], true, "{:?}", response(&messages, seq)); }
+    assert_eq!(response(&messages, 3)["body"]["breakpoints"], json!([
+        { "id" : 0, "verified" : true,  "line" : 1 },
+        { "id" : 1, "verified" : true,  "line" : 3 },
+        { "id" : 2, "verified" : false, "line" : 4 }
+    ]));
+    assert_eq!(response(&messages, 7)["body"]["stackFrames"][0]["name"], "print 2");
+    assert_eq!(response(&messages, 7)["body"]["stackFrames"][0]["line"], 3);
+    assert_eq!(response(&messages, 7)["body"]["stackFrames"][0]["source"]["path"], program.as_str());
+    assert_eq!(response(&messages, 8)["body"]["scopes"][0]["name"], "queue");
+    let queue = response(&messages, 9)["body"]["variables"].as_array().unwrap().iter()
+        .map(|variable| format!("{} {}", variable["name"].as_str().unwrap(), variable["value"].as_str().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(queue, ["1 print 2", "2 print 3", "3 print 4"]);
+    assert_eq!(response(&messages, 10)["body"]["result"], "4");
+
+    let events = events(&messages);
+    let names  = events.iter().map(|(event, _,)| *event).collect::<Vec<_>>();
+    assert_eq!(names, ["initialized", "stopped", "output", "stopped", "output", "stopped", "output", "exited", "terminated"]);
+    assert_eq!(events[1].1["reason"], "breakpoint");
+    assert_eq!(events[1].1["hitBreakpointIds"], json!([0]));
+    assert_eq!(events[2].1["output"], "1\n");
+    assert_eq!(events[3].1["hitBreakpointIds"], json!([1]));
+    assert_eq!(events[4].1["output"], "2\n");
+    assert_eq!(events[5].1["reason"], "step");
+    // print 4 was pushed by line 1, so the breakpoint there doesn't stop it.
+    assert_eq!(events[6].1["output"], "3\n4\n");
+    assert_eq!(events[7].1["exitCode"], 0);
+}
+
+#[test]
+fn stops_on_entry_errors_and_exit() {
+    let dir     = TempDir::new().unwrap();
+    let program = script(&dir, "print 1\n/ 1 0\nexit 3\nprint 2\n");
+    let messages = session(&[
+        ("initialize",        json!({})),
+        ("launch",            json!({ "program" : program, "stopOnEntry" : true })),
+        ("configurationDone", json!({})),
+        ("continue",          json!({ "threadId" : 1 })),
+        ("continue",          json!({ "threadId" : 1 }))
+    ]);
+    let events = events(&messages);
+    let names  = events.iter().map(|(event, _,)| *event).collect::<Vec<_>>();
+    assert_eq!(names, ["initialized", "stopped", "output", "stopped", "exited", "terminated"]);
+    assert_eq!(events[1].1["reason"], "entry");
+    assert_eq!(events[3].1["reason"], "exception");
+    assert!(events[3].1["text"].as_str().unwrap().contains("division_by_zero"));
+    assert_eq!(events[4].1["exitCode"], 3);
+}
+
+#[test]
+fn failed_requests() {
+    let dir     = TempDir::new().unwrap();
+    let program = script(&dir, "print (\n");
+    let messages = session(&[
+        ("setBreakpoints", json!({ "source" : { "path" : program }, "breakpoints" : [{ "line" : 1 }] })),
+        ("next",           json!({ "threadId" : 1 })),
+        ("launch",         json!({ "program" : dir.path().join("missing.push") })),
+        ("launch",         json!({ "program" : program })),
+        ("stepBack",       json!({ "threadId" : 1 }))
+    ]);
+    for seq in 1..=5 { assert_eq!(response(&messages, seq)["success"], false, "{:?}", response(&messages, seq)); }
+    assert!(response(&messages, 4)["message"].as_str().unwrap().contains("Failed to parse line 1"));
+    assert!(events(&messages).is_empty());
+
+    let program = script(&dir, "print 1\n");
+    let messages = session(&[
+        ("launch",         json!({ "program" : program })),
+        ("setBreakpoints", json!({ "source" : { "path" : "elsewhere.push" }, "breakpoints" : [{ "line" : 1 }] })),
+        ("evaluate",       json!({ "expression" : "print (" }))
+    ]);
+    assert_eq!(response(&messages, 2)["body"]["breakpoints"][0]["verified"], false);
+    assert_eq!(response(&messages, 3)["success"], false);
+}
+
+#[test]
+fn framing() {
+    let mut buffer = Vec::new();
+    dap::write_message(&mut buffer, &json!({ "text" : "ünïcode" })).unwrap();
+    dap::write_message(&mut buffer, &json!([1, 2])).unwrap();
+    let mut reader = buffer.as_slice();
+    assert_eq!(dap::read_message(&mut reader).unwrap(), Some(json!({ "text" : "ünïcode" })));
+    assert_eq!(dap::read_message(&mut reader).unwrap(), Some(json!([1, 2])));
+    assert_eq!(dap::read_message(&mut reader).unwrap(), None);
+
+    assert!(dap::read_message(&mut "Content-Type: json\r\n\r\n{}".as_bytes()).is_err());
+    assert!(dap::read_message(&mut "Content-Length: 10\r\n\r\n{}".as_bytes()).is_err());
+}
@@ -0,0 +1,134 @@
+use sisyphus::debug::{ Breakpoint, Debugger, Pause };
+use sisyphus::exec::{ Capture, ErrorKind, Executor, Reader, Value };
+use sisyphus::parser;
+
+
+fn debugger(script : &str) -> (Debugger, Capture,) {
+    let output = Capture::new();
+    let mut e = Executor::new().with_output(output.clone());
+    e.push_exprs(parser::parse(script).unwrap());
+    (Debugger::new(e), output,)
+}
+
+fn expr(text : &str) -> sisyphus::expr::Expr {
+    parser::parse(text).unwrap().remove(0)
+}
+
+
+#[test]
+fn breakpoints_pause_before_the_expression() {
+    let (mut d, output,) = debugger("print 1\nprint 2\npushes queue (gets queue 1 2)\nprint 3");
+    let tick    = d.add_breakpoint(Breakpoint::Tick(1));
+    let pattern = d.add_breakpoint("expr pushes".parse().unwrap());
+
+    assert_eq!(d.resume(), Pause::Breakpoint(tick));
+    assert_eq!(d.executor().ticks(), 0);
+    assert_eq!(d.resume(), Pause::Breakpoint(pattern));
+    assert_eq!(d.executor().ticks(), 2);
+    assert_eq!(output.take(), "1\n2\n");
+
+    let len = d.add_breakpoint(Breakpoint::QueueLength(0));
+    assert_eq!(d.resume(), Pause::Breakpoint(len));
+    assert_eq!(output.take(), "3\n3\n");
+    assert!(d.remove_breakpoint(len));
+    assert!(! d.remove_breakpoint(len));
+    assert_eq!(d.resume(), Pause::Completed);
+    assert_eq!(output.take(), "");
+    assert_eq!(d.breakpoints().map(|(id, _,)| id).collect::<Vec<_>>(), [tick, pattern]);
+}
+
+#[test]
+fn stepping_ignores_breakpoints() {
+    let (mut d, _,) = debugger("+ 1 2\n* 2 3");
+    d.add_breakpoint(Breakpoint::Tick(2));
+    assert_eq!(d.step(), Pause::Step);
+    assert_eq!(d.last_value(), Some(&Value::Int(3)));
+    assert_eq!(d.step(), Pause::Step);
+    assert_eq!(d.last_value(), Some(&Value::Int(6)));
+    assert_eq!(d.step(), Pause::Completed);
+}
+
+#[test]
+fn watchpoints_fire_when_an_index_changes() {
+    let (mut d, _,) = debugger("set queue 2 \"print 5\"\nprint 1\nprint 2\nprint 3");
+    d.watch(2);
+    assert_eq!(d.resume(), Pause::Watchpoint { index : 2, before : Some(expr("print 1")), after : Some(expr("print 5")) });
+    assert_eq!(d.resume(), Pause::Watchpoint { index : 2, before : Some(expr("print 5")), after : Some(expr("print 3")) });
+    assert!(d.unwatch(2));
+    assert!(! d.unwatch(2));
+    assert_eq!(d.resume(), Pause::Completed);
+}
+
+#[test]
+fn errors_and_exit_pause() {
+    let (mut d, _,) = debugger("/ 1 0\nexit 4\nprint 1");
+    match (d.resume()) {
+        Pause::Failed(err) => assert_eq!(err.kind.name(), "division_by_zero"),
+        pause              => panic!("{:?}", pause)
+    }
+    assert_eq!(d.resume(), Pause::Halted(4));
+    assert_eq!(d.step(), Pause::Halted(4));
+    assert_eq!(d.executor().ticks(), 2);
+}
+
+#[test]
+fn evaluating_leaves_the_queue_alone() {
+    let (mut d, output,) = debugger("print 1\nprint 2");
+    d.step();
+    let before = d.executor().get_exprs(0, 2).unwrap().into_iter().cloned().collect::<Vec<_>>();
+
+    assert_eq!(d.evaluate(&expr("push queue \"print 3\"")), Value::ExprQueue);
+    assert_eq!(d.evaluate(&expr("len queue")), Value::Int(2));
+    assert_eq!(d.evaluate(&expr("get queue 0")), Value::String("print 1".to_string()));
+    d.evaluate(&expr("print \"eval\""));
+    d.evaluate(&expr("exit 1"));
+
+    assert_eq!(d.executor().get_exprs(0, 2).unwrap().into_iter().cloned().collect::<Vec<_>>(), before);
+    assert_eq!(d.executor().len_exprs(), 2);
+    assert_eq!(d.executor().ticks(), 1);
+    assert_eq!(d.executor().halted(), None);
+    assert_eq!(d.resume(), Pause::Completed);
+    assert_eq!(output.take(), "1\neval\n2\n");
+}
+
+#[test]
+fn evaluating_leaves_channels_spawned_scripts_and_input_alone() {
+    let output = Capture::new();
+    let mut e = Executor::new().with_output(output.clone()).with_input(Reader::new("line\n".as_bytes()));
+    e.push_exprs(parser::parse("send (channel \"c\") 1\nprint recv (channel \"c\")\nprint readln").unwrap());
+    let mut d = Debugger::new(e);
+    d.step();
+
+    for (text, keyword,) in [
+        ("send (channel \"c\") 2", "send"),
+        ("recv (channel \"c\")",   "recv"),
+        ("channel \"d\"",          "channel"),
+        ("spawn \"print 9\"",      "spawn"),
+        ("readln",                 "readln"),
+        ("readall",                "readall")
+    ] {
+        let Value::Error(err) = d.evaluate(&expr(text))
+            else { panic!("expected {} to fail", text); };
+        assert_eq!((err.kind, err.message,), (ErrorKind::Disabled, format!("{} can't be evaluated outside of the queue", keyword),));
+    }
+
+    assert_eq!(d.executor().channel_len("c"), 1);
+    assert!(d.executor_mut().take_spawned().is_empty());
+    assert_eq!(d.resume(), Pause::Completed);
+    assert_eq!(output.take(), "1\nline\n");
+}
+
+#[test]
+fn parsing_breakpoints() {
+    assert_eq!("tick 5".parse(), Ok(Breakpoint::Tick(5)));
+    assert_eq!("len 0".parse(), Ok(Breakpoint::QueueLength(0)));
+    assert_eq!("expr push queue".parse(), Ok(Breakpoint::Pattern("push queue".to_string())));
+    assert!("tick".parse::<Breakpoint>().is_err());
+    assert!("expr".parse::<Breakpoint>().is_err());
+    assert!("line 3".parse::<Breakpoint>().is_err());
+    assert_eq!("line a:b.push:3".parse(), Ok(Breakpoint::Line { file : "a:b.push".to_string(), line : 3 }));
+    assert!("line a.push".parse::<Breakpoint>().is_err());
+    for breakpoint in [Breakpoint::Tick(5), Breakpoint::QueueLength(2), Breakpoint::Pattern("print".to_string()), Breakpoint::Line { file : "a.push".to_string(), line : 1 }] {
+        assert_eq!(breakpoint.to_string().parse(), Ok(breakpoint));
+    }
+}
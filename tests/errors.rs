@@ -0,0 +1,60 @@
+mod common;
+use common::eval;
+use sisyphus::exec::{ Executor, Value, ErrorKind };
+use sisyphus::exec::vfs::Memory;
+
+
+fn error(script : &str) -> (ErrorKind, String,) {
+    let mut e = Executor::new().with_file_system(Memory::new());
+    let Value::Error(err) = eval(&mut e, script)
+        else { panic!("expected {} to fail", script); };
+    (err.kind, err.expr.expect("failed errors know their expression").to_string(),)
+}
+
+fn strings(values : &[&str]) -> Value {
+    Value::Array(values.iter().map(|v| Value::String(v.to_string())).collect())
+}
+
+
+#[test]
+fn each_kind_of_error() {
+    for (script, kind,) in [
+        ("- 1 \"a\"",        ErrorKind::TypeMismatch),
+        ("get \"ab\" 5",     ErrorKind::OutOfRange),
+        ("/ 1 0",            ErrorKind::DivisionByZero),
+        ("int \"one\"",      ErrorKind::Conversion),
+        ("push queue \"+\"", ErrorKind::Syntax),
+        ("fsread \"none\"",  ErrorKind::Io)
+    ] {
+        assert_eq!(error(script).0, kind, "{}", script);
+    }
+}
+
+#[test]
+fn reason_gives_the_kind_and_message() {
+    let mut e = Executor::new();
+    assert_eq!(eval(&mut e, "reason / 1 0"), strings(&["division_by_zero", "division by zero"]));
+    assert_eq!(eval(&mut e, "get reason int \"one\" 0"), Value::String("conversion".to_string()));
+    assert_eq!(eval(&mut e, "reason 1"), Value::Unit);
+    assert_eq!(eval(&mut e, "reason reason / 1 0"), Value::Unit);
+}
+
+#[test]
+fn errors_pass_through_arithmetic_unchanged() {
+    assert_eq!(error("+ 1 * 2 / 1 0"), (ErrorKind::DivisionByZero, "/ 1 0".to_string(),));
+    assert_eq!(error("- (int \"one\") (/ 1 0)"), (ErrorKind::Conversion, "int \"one\"".to_string(),));
+    assert_eq!(error("* 2 % 5 int \"x\""), (ErrorKind::Conversion, "int \"x\"".to_string(),));
+    let mut e = Executor::new();
+    assert_eq!(eval(&mut e, "! / 1 0"), Value::Unit);
+    assert_eq!(eval(&mut e, "get reason + \"a\" / 1 0 0"), Value::String("division_by_zero".to_string()));
+}
+
+#[test]
+fn errors_equal_each_other_when_their_kinds_match() {
+    let mut e = Executor::new();
+    assert_eq!(eval(&mut e, "= (/ 1 0) (% 2 0)"), Value::Bool(true));
+    assert_eq!(eval(&mut e, "= (- 1 \"a\") (* \"b\" queue)"), Value::Bool(true));
+    assert_eq!(eval(&mut e, "= (/ 1 0) (int \"one\")"), Value::Bool(false));
+    assert_eq!(eval(&mut e, "= (/ 1 0) 0"), Value::Bool(false));
+    assert_eq!(eval(&mut e, "! = (- 1 \"a\") (/ 2 0)"), Value::Bool(true));
+}
@@ -0,0 +1,76 @@
+mod common;
+use common::{ eval, error };
+use sisyphus::exec::{ Executor, Value, ErrorKind };
+use sisyphus::expr::Lit;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+
+fn path(p : &Path) -> String {
+    Lit::String(p.to_string_lossy().into_owned()).to_string()
+}
+
+
+#[test]
+fn fswrite_then_fsread_round_trips() {
+    let dir  = TempDir::new().unwrap();
+    let file = path(&dir.path().join("out.txt"));
+    let text = "line one\nline \"two\"\t\u{e9}";
+
+    assert_eq!(eval(&mut Executor::new(), &format!("fswrite {} {}", file, Lit::String(text.to_string()))), Value::Unit);
+    assert_eq!(fs::read_to_string(dir.path().join("out.txt")).unwrap(), text);
+    assert_eq!(eval(&mut Executor::new(), &format!("fsread {}", file)), Value::String(text.to_string()));
+}
+
+#[test]
+fn fswrite_overwrites() {
+    let dir  = TempDir::new().unwrap();
+    let file = path(&dir.path().join("out.txt"));
+    eval(&mut Executor::new(), &format!("fswrite {} \"a longer first version\"", file));
+    eval(&mut Executor::new(), &format!("fswrite {} \"short\"", file));
+    assert_eq!(fs::read_to_string(dir.path().join("out.txt")).unwrap(), "short");
+}
+
+#[test]
+fn fbwrite_then_fbread_round_trips() {
+    let dir   = TempDir::new().unwrap();
+    let file  = path(&dir.path().join("out.bin"));
+    let bytes = (0..=255).rev().collect::<Vec<u8>>();
+
+    assert_eq!(eval(&mut Executor::new(), &format!("fbwrite {} (! range 0 256)", file)), Value::Unit);
+    assert_eq!(fs::read(dir.path().join("out.bin")).unwrap(), bytes);
+    assert_eq!(
+        eval(&mut Executor::new(), &format!("fbread {}", file)),
+        Value::Array(bytes.iter().map(|&b| Value::Int(b as i128)).collect())
+    );
+}
+
+#[test]
+fn fbread_copies_fbwrite() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("in.bin"), [0u8, 1, 127, 128, 255, b'\n']).unwrap();
+    let script = format!("fbwrite {} (fbread {})", path(&dir.path().join("out.bin")), path(&dir.path().join("in.bin")));
+    assert_eq!(eval(&mut Executor::new(), &script), Value::Unit);
+    assert_eq!(fs::read(dir.path().join("out.bin")).unwrap(), [0u8, 1, 127, 128, 255, b'\n']);
+}
+
+#[test]
+fn io_failures_are_errors() {
+    let dir     = TempDir::new().unwrap();
+    let missing = path(&dir.path().join("missing").join("file"));
+    assert_eq!(error(&mut Executor::new(), &format!("fsread {}", missing)), ErrorKind::Io);
+    assert_eq!(error(&mut Executor::new(), &format!("fbread {}", missing)), ErrorKind::Io);
+    assert_eq!(error(&mut Executor::new(), &format!("fswrite {} \"x\"", missing)), ErrorKind::Io);
+    assert_eq!(error(&mut Executor::new(), &format!("fbwrite {} (range 0 3)", missing)), ErrorKind::Io);
+}
+
+#[test]
+fn fbwrite_rejects_non_bytes() {
+    let dir  = TempDir::new().unwrap();
+    let file = path(&dir.path().join("out.bin"));
+    assert_eq!(error(&mut Executor::new(), &format!("fbwrite {} (range 250 260)", file)), ErrorKind::OutOfRange);
+    assert_eq!(error(&mut Executor::new(), &format!("fbwrite {} (push (range 0 1) \"a\")", file)), ErrorKind::TypeMismatch);
+    assert_eq!(error(&mut Executor::new(), &format!("fswrite {} 12", file)), ErrorKind::TypeMismatch);
+    assert!(! dir.path().join("out.bin").exists());
+}
@@ -0,0 +1,78 @@
+mod common;
+use common::eval;
+use sisyphus::exec::{ Capture, Executor, Limits, Outcome, Reader, Value, ErrorKind };
+use sisyphus::parser;
+use std::io::Cursor;
+
+
+fn input(text : &str) -> Reader<Cursor<Vec<u8>>> {
+    Reader::new(Cursor::new(text.as_bytes().to_vec()))
+}
+
+
+#[test]
+fn readln_reads_lines_until_the_end() {
+    let mut e = Executor::new().with_input(input("first\r\nsecond\n\nlast"));
+    assert_eq!(eval(&mut e, "readln"), Value::String("first".to_string()));
+    assert_eq!(eval(&mut e, "+ readln \"!\""), Value::String("second!".to_string()));
+    assert_eq!(eval(&mut e, "readln"), Value::String("".to_string()));
+    assert_eq!(eval(&mut e, "readln"), Value::String("last".to_string()));
+    let Value::Error(err) = eval(&mut e, "readln")
+        else { panic!("expected the input to have ended"); };
+    assert_eq!(err.kind, ErrorKind::EndOfInput);
+}
+
+#[test]
+fn readall_reads_the_rest() {
+    let mut e = Executor::new().with_input(input("first\nsecond\nthird\n"));
+    assert_eq!(eval(&mut e, "readln"), Value::String("first".to_string()));
+    assert_eq!(eval(&mut e, "readall"), Value::String("second\nthird\n".to_string()));
+    assert_eq!(eval(&mut e, "readall"), Value::String("".to_string()));
+}
+
+#[test]
+fn a_filter() {
+    let output = Capture::new();
+    let mut e  = Executor::new().with_input(input("a\nb\n")).with_output(output.clone());
+    e.push_exprs(parser::parse("print + \"> \" readln\nprint + \"> \" readln").unwrap());
+    assert_eq!(e.run(Limits::unlimited()).outcome, Outcome::Completed);
+    assert_eq!(output.contents(), "> a\n> b\n");
+}
+
+#[test]
+fn queue_source_adds_a_line_per_tick() {
+    let output = Capture::new();
+    let mut e  = Executor::new()
+        .with_output(output.clone())
+        .with_queue_source(input("print 1\n\n// nothing\nprint (+ 1 1)\nprint len queue\n"));
+    e.push_exprs(parser::parse("print \"start\"").unwrap());
+    let report = e.run(Limits::unlimited());
+    assert_eq!(report.outcome, Outcome::Completed);
+    assert_eq!(report.ticks, 4);
+    assert_eq!(output.contents(), "start\n1\n2\n1\n");
+}
+
+#[test]
+fn queue_source_keeps_lines_numbered() {
+    let mut e = Executor::new().with_output(Capture::new()).with_queue_source(input("print 1\nprint (get (range 0 1) 4)\n"));
+    let Outcome::Failed(err) = e.run(Limits::unlimited().with_stop_on_error()).outcome
+        else { panic!("expected a failure"); };
+    assert_eq!(err.expr.unwrap().span.to_string(), "<stdin>:2:8");
+}
+
+#[test]
+fn queue_source_syntax_errors_fail() {
+    let mut e = Executor::new().with_output(Capture::new()).with_queue_source(input("print 1\nprint (\n"));
+    let Outcome::Failed(err) = e.run(Limits::unlimited()).outcome
+        else { panic!("expected a failure"); };
+    assert_eq!(err.kind, ErrorKind::Syntax);
+    assert_eq!(err.message, "cannot parse line 2 of <stdin>");
+}
+
+#[test]
+fn step_pulls_from_the_queue_source() {
+    let output = Capture::new();
+    let mut e  = Executor::new().with_output(output.clone()).with_queue_source(input("print 1\nprint 2\n"));
+    while (e.tick()) { }
+    assert_eq!(output.contents(), "1\n2\n");
+}
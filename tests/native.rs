@@ -0,0 +1,74 @@
+mod common;
+use common::{ eval, error };
+use sisyphus::exec::{ Capture, Executor, Limits, Outcome, RuntimeError, Scheduler, Value, ErrorKind };
+use sisyphus::parser;
+use std::sync::{ Arc, Mutex };
+
+
+fn executor() -> Executor {
+    Executor::new().with_output(Capture::new())
+        .with_native("double", 1, |_, args| args[0].clone() + args[0].clone())
+        .with_native("answer", 0, |_, _| Value::Int(42))
+        .with_native("join", 3, |_, args| Value::String(args.iter().map(Value::to_string).collect::<Vec<_>>().join(" ")))
+}
+
+
+#[test]
+fn scripts_call_registered_functions() {
+    let mut e = executor();
+    assert_eq!(eval(&mut e, "call \"double\" 21"), Value::Int(42));
+    assert_eq!(eval(&mut e, "call \"answer\""), Value::Int(42));
+    assert_eq!(eval(&mut e, "call \"join\" 1 (+ 1 1) \"three\""), Value::String("1 2 three".to_string()));
+    assert_eq!(eval(&mut e, "+ (call \"double\" 1) (call \"double\" 2)"), Value::Int(6));
+    // The arguments run on, even onto indented lines.
+    assert_eq!(eval(&mut e, "call \"join\" 1\n    (call \"double\" 2)\n    3"), Value::String("1 4 3".to_string()));
+    assert_eq!(error(&mut e, "call \"join\" 1 call \"double\" 2 3"), ErrorKind::Arity);
+    assert_eq!(e.native_names().collect::<Vec<_>>(), ["answer", "double", "join"]);
+}
+
+#[test]
+fn functions_can_use_the_executor() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let record = seen.clone();
+    let mut e = executor()
+        .with_native("note", 1, move |e, args| {
+            record.lock().unwrap().push((e.ticks(), args[0].clone(),));
+            e.push_exprs(parser::parse("print \"noted\"").unwrap());
+            Value::Unit
+        })
+        .with_native("fail", 0, |_, _| RuntimeError::new(ErrorKind::Io, "the host is down").into());
+    e.push_exprs(parser::parse("call \"note\" (call \"double\" 2)\ncall \"fail\"").unwrap());
+    let report = e.run(Limits::new(10).with_stop_on_error());
+    assert!(matches!(report.outcome, Outcome::Failed(err) if (err.kind == ErrorKind::Io)));
+    assert_eq!(*seen.lock().unwrap(), [(1, Value::Int(4),)]);
+
+    // Registering again replaces the function.
+    e.register("double", 1, |_, args| args[0].clone() * Value::Int(3));
+    assert_eq!(eval(&mut e, "call \"double\" 2"), Value::Int(6));
+}
+
+#[test]
+fn unknown_names_and_wrong_arity_fail() {
+    let mut e = executor();
+    assert_eq!(error(&mut e, "call \"triple\" 1"), ErrorKind::UnknownFunction);
+    assert_eq!(error(&mut e, "call \"double\""), ErrorKind::Arity);
+    assert_eq!(error(&mut e, "call \"double\" 1 2"), ErrorKind::Arity);
+    assert_eq!(error(&mut e, "call 1"), ErrorKind::TypeMismatch);
+
+    let Value::Error(err) = eval(&mut e, "call \"answer\" 1")
+        else { panic!("expected the call to fail"); };
+    assert_eq!(err.message, "answer takes 0 arguments, but was given 1");
+    let Value::Error(err) = eval(&mut e, "call \"triple\"")
+        else { panic!("expected the call to fail"); };
+    assert_eq!(err.message, "there is no function named \"triple\"");
+}
+
+#[test]
+fn spawned_executors_inherit_functions() {
+    let output = Capture::new();
+    let mut main = executor().with_output(output.clone());
+    main.push_exprs(parser::parse("spawn \"print (call \\\"double\\\" 4)\"").unwrap());
+    let spawned = output.clone();
+    let report = Scheduler::new(main).with_spawner(move || Executor::new().with_output(spawned.clone())).run(Limits::new(10));
+    assert_eq!((report.outcome, output.contents().as_str(),), (Outcome::Completed, "8\n"));
+}
@@ -0,0 +1,127 @@
+use sisyphus::exec::{ Capture, Executor, Observer, Trace, TraceEvent, Value };
+use sisyphus::expr::Expr;
+use sisyphus::parser;
+use std::sync::{ Arc, Mutex };
+
+
+#[derive(Clone, Default)]
+struct Log {
+    name   : &'static str,
+    events : Arc<Mutex<Vec<String>>>
+}
+
+impl Observer for Log {
+    fn before_tick(&mut self, tick : u64, expr : &Expr) {
+        self.events.lock().unwrap().push(format!("{} before {} {}", self.name, tick, expr));
+    }
+    fn after_tick(&mut self, tick : u64, _expr : &Expr, value : &Value) {
+        self.events.lock().unwrap().push(format!("{} after {} {}", self.name, tick, value));
+    }
+    fn on_push(&mut self, expr : &Expr) {
+        self.events.lock().unwrap().push(format!("{} push {}", self.name, expr));
+    }
+    fn on_set(&mut self, index : usize, expr : &Expr) {
+        self.events.lock().unwrap().push(format!("{} set {} {}", self.name, index, expr));
+    }
+    fn on_insert(&mut self, index : usize, expr : &Expr) {
+        self.events.lock().unwrap().push(format!("{} insert {} {}", self.name, index, expr));
+    }
+    fn on_print(&mut self, value : &Value) {
+        self.events.lock().unwrap().push(format!("{} print {}", self.name, value));
+    }
+}
+
+// Only sees ticks.
+struct Count(Arc<Mutex<u64>>);
+
+impl Observer for Count {
+    fn after_tick(&mut self, _tick : u64, _expr : &Expr, _value : &Value) {
+        *self.0.lock().unwrap() += 1;
+    }
+}
+
+
+#[test]
+fn observers_see_ticks_queue_changes_and_output() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut e = Executor::new().with_output(Capture::new())
+        .with_observer(Log { name : "a", events : events.clone() });
+    e.push_exprs(parser::parse("print 1\nset (insert queue 1 2) 1 3\nprint 4").unwrap());
+    while (e.tick()) { }
+    assert_eq!(*events.lock().unwrap(), [
+        "a push print 1",
+        "a push set insert queue 1 2 1 3",
+        "a push print 4",
+        "a before 1 print 1",
+        "a print 1",
+        "a after 1 1",
+        "a before 2 set insert queue 1 2 1 3",
+        "a insert 1 2",
+        "a set 1 3",
+        "a after 2 exprqueue",
+        "a before 3 3",
+        "a after 3 3",
+        "a before 4 print 4",
+        "a print 4",
+        "a after 4 4"
+    ]);
+}
+
+#[test]
+fn observers_are_called_in_the_order_they_were_added() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let count  = Arc::new(Mutex::new(0));
+    let mut e = Executor::new().with_output(Capture::new())
+        .with_observer(Log { name : "a", events : events.clone() })
+        .with_observer(Count(count.clone()))
+        .with_observer(Log { name : "b", events : events.clone() });
+    e.push_exprs(parser::parse("print 1").unwrap());
+    e.tick();
+    assert_eq!(*events.lock().unwrap(), [
+        "a push print 1", "b push print 1",
+        "a before 1 print 1", "b before 1 print 1",
+        "a print 1", "b print 1",
+        "a after 1 1", "b after 1 1"
+    ]);
+    assert_eq!(*count.lock().unwrap(), 1);
+
+    assert_eq!(e.take_observers().len(), 3);
+    e.push_exprs(parser::parse("print 2").unwrap());
+    e.tick();
+    assert_eq!(events.lock().unwrap().len(), 8);
+    assert_eq!(*count.lock().unwrap(), 1);
+}
+
+#[test]
+fn evaluating_is_not_observed() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut e = Executor::new().with_output(Capture::new())
+        .with_observer(Log { name : "a", events : events.clone() });
+    e.evaluate(&parser::parse("print (push queue 1)").unwrap().remove(0));
+    assert!(events.lock().unwrap().is_empty());
+}
+
+#[test]
+fn observers_see_inserts_at_the_same_index_as_the_trace() {
+    let events   = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Capture::new();
+    let mut e = Executor::new().with_output(Capture::new())
+        .with_observer(Log { name : "a", events : events.clone() });
+    e.start_trace(recorded.clone());
+    e.push_exprs(parser::parse("insert queue 2 \"print 2\"\ninsert queue 0 \"print 1\"\nprint 3").unwrap());
+    e.tick();
+    e.tick();
+    e.stop_trace().unwrap();
+
+    let observed = events.lock().unwrap().iter()
+        .filter_map(|event| event.strip_prefix("a insert ").map(str::to_string))
+        .collect::<Vec<_>>();
+    let traced = Trace::read(recorded.contents().as_bytes()).unwrap().events.into_iter()
+        .filter_map(|event| match (event) {
+            TraceEvent::Insert(index, expr) => Some(format!("{} {}", index, expr)),
+            _                               => None
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(observed, ["2 print 2", "1 insert queue 0 \"print 1\""]);
+    assert_eq!(observed, traced);
+}
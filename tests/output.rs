@@ -0,0 +1,37 @@
+use sisyphus::exec::{ Capture, Executor, Limits, Outcome };
+use sisyphus::parser;
+use std::fs;
+
+
+fn run_sample(name : &str) -> String {
+    let source  = fs::read_to_string(format!("samples/{}", name)).unwrap();
+    let capture = Capture::new();
+    let mut e   = Executor::new().with_output(capture.clone());
+    e.push_exprs(parser::parse(&source).unwrap());
+    assert_eq!(e.run(Limits::new(100_000)).outcome, Outcome::Completed);
+    capture.contents()
+}
+
+
+#[test]
+fn sqrt_counts_down() {
+    assert_eq!(run_sample("sqrt.push"), "10\n9\n8\n7\n6\n5\n4\n3\n2\n1\n0\n");
+}
+
+#[test]
+fn prime_sieve_says_hi() {
+    assert_eq!(run_sample("prime_sieve.push"), "hi\n");
+}
+
+#[test]
+fn executors_keep_their_output_apart() {
+    let (a, b,) = (Capture::new(), Capture::new());
+    let mut ea  = Executor::new().with_output(a.clone());
+    let mut eb  = Executor::new().with_output(b.clone());
+    ea.push_exprs(parser::parse("print \"a1\"\nprint \"a2\"").unwrap());
+    eb.push_exprs(parser::parse("print \"b1\"\nprint \"b2\"").unwrap());
+    while (ea.tick() | eb.tick()) { }
+    assert_eq!(a.contents(), "a1\na2\n");
+    assert_eq!(b.take(), "b1\nb2\n");
+    assert_eq!(b.contents(), "");
+}
@@ -0,0 +1,199 @@
+use sisyphus::expr::{ Expr, ExprKind };
+use sisyphus::exec::{ Executor, Execute, Value };
+use sisyphus::parser::{ self, Render };
+use sisyphus::span::Origin;
+use std::fs;
+
+
+fn parses_like(script : &str, plain : &str) {
+    assert_eq!(parser::parse(script).unwrap(), parser::parse(plain).unwrap(), "{:?}", script);
+}
+
+/// The line and column of every diagnostic `script` fails with.
+fn errors(script : &str) -> Vec<(usize, usize,)> {
+    parser::parse(script).unwrap_err().diagnostics.iter().map(|d| (d.line, d.column,)).collect()
+}
+
+
+#[test]
+fn comments_are_ignored() {
+    parses_like("print 1 // one\n// two\nprint 2", "print 1\nprint 2");
+    parses_like("print /* one */ 1\n/* two\n   three */\nprint 2", "print 1\nprint 2");
+    parses_like("/* outer /* inner */ still a comment */ print 1", "print 1");
+    parses_like("print 1\n// the end", "print 1");
+}
+
+#[test]
+fn blank_lines_are_ignored() {
+    parses_like("\n\nprint 1\n\n  \n\t\nprint 2\n\n", "print 1\nprint 2");
+    assert_eq!(parser::parse("").unwrap(), []);
+    assert_eq!(parser::parse("\n  \n").unwrap(), []);
+}
+
+#[test]
+fn crlf_line_endings_are_accepted() {
+    parses_like("print 1\r\nprint 2\r\n", "print 1\nprint 2");
+    parses_like("print 1 // one\r\n\r\nprint 2", "print 1\nprint 2");
+}
+
+#[test]
+fn a_byte_order_mark_is_skipped() {
+    parses_like("\u{feff}print 1\nprint 2", "print 1\nprint 2");
+}
+
+#[test]
+fn a_shebang_is_skipped_on_the_first_line_only() {
+    parses_like("#!/usr/bin/env sisyphus run\nprint 1", "print 1");
+    parses_like("\u{feff}#!/usr/bin/env sisyphus run\r\nprint 1", "print 1");
+    assert!(parser::parse("print 1\n#!/usr/bin/env sisyphus run").is_err());
+}
+
+#[test]
+fn errors_point_at_the_failing_column() {
+    assert_eq!(errors("+ 1 ?"), [(1, 5,)]);
+    assert_eq!(errors("print 1\n\n  // note\n+ 1 ?"), [(4, 5,)]);
+    assert_eq!(errors("print \"é\" ?"), [(1, 11,)]);
+}
+
+#[test]
+fn errors_after_a_byte_order_mark_count_columns_from_the_first_visible_character() {
+    assert_eq!(errors("\u{feff}+ 1 ?"), [(1, 5,)]);
+    assert_eq!(parser::parse("\u{feff}+ 1 ?").unwrap_err().diagnostics[0].source, "+ 1 ?");
+}
+
+#[test]
+fn errors_with_crlf_line_endings_are_on_the_right_line_and_column() {
+    assert_eq!(errors("print 1\r\n+ 1 ?\r\nprint 2\r\n"), [(2, 5,)]);
+    assert_eq!(parser::parse("print 1\r\n+ 1 ?\r\n").unwrap_err().diagnostics[0].source, "+ 1 ?");
+}
+
+
+#[test]
+fn groups_parse_like_their_contents() {
+    for (grouped, plain,) in [
+        ("(+ 1 2)",                 "+ 1 2"),
+        ("+ (1) ( 2 )",             "+ 1 2"),
+        ("print (- (int \"3\") 1)", "print - int \"3\" 1"),
+        ("((queue))",               "queue")
+    ] {
+        assert_eq!(parser::parse(grouped).unwrap(), parser::parse(plain).unwrap(), "{}", grouped);
+    }
+    assert!(parser::parse("(+ 1 2").is_err());
+    assert!(parser::parse("(+ 1) 2").is_err());
+}
+
+#[test]
+fn indented_lines_continue_an_expression() {
+    let plain = parser::parse("+ 1 2").unwrap();
+    for script in [
+        "+\n    1\n    2",
+        "+ 1\n\t2",
+        "+\n    1\n\n    2",
+        "+\n    1\n   \n    2",
+        "+\n    1 // one\n    // two\n\n    2",
+        "+\r\n    1\r\n\r\n    2"
+    ] {
+        assert_eq!(parser::parse(script).unwrap(), plain, "{:?}", script);
+    }
+    assert_eq!(parser::parse("+ 1 2\n\nprint 3").unwrap().len(), 2);
+    assert!(parser::parse("+\n1\n2").is_err());
+    assert!(parser::parse("+ 1\n\n2").is_err());
+}
+
+#[test]
+fn lesspain_sample_matches_its_one_line_form() {
+    let lesspain = parser::parse(&fs::read_to_string("samples/sqrt.push.lesspain").unwrap()).unwrap();
+    let plain    = parser::parse(&fs::read_to_string("samples/sqrt.push").unwrap()).unwrap();
+    assert_eq!(lesspain, plain);
+}
+
+#[test]
+fn parsed_expressions_keep_their_spans() {
+    let exprs = parser::parse_with_origin("print 1\n+ 2\n    (- 3 4)", Origin::file("main.push")).unwrap();
+    let span  = |expr : &Expr| (expr.span.origin.clone(), expr.span.line, expr.span.column,);
+    let file  = Origin::file("main.push");
+    assert_eq!(span(&exprs[0]), (file.clone(), 1, 1,));
+    let ExprKind::Builtin(_, args) = &exprs[1].kind
+        else { panic!("expected a builtin"); };
+    assert_eq!(span(&exprs[1]), (file.clone(), 2, 1,));
+    assert_eq!(span(&args[0]), (file.clone(), 2, 3,));
+    assert_eq!(span(&args[1]), (file.clone(), 3, 6,));
+    assert_eq!(exprs[1].span.to_string(), "main.push:2:1");
+}
+
+#[test]
+fn pushed_strings_remember_what_pushed_them() {
+    let mut e = Executor::new();
+    e.push_exprs(parser::parse_with_origin("+ 0 0\npush queue \"+ 1 1\"", Origin::file("main.push")).unwrap());
+    e.tick();
+    e.tick();
+    let pushed = e.get_expr(1).unwrap();
+    let Origin::Pushed { by, tick } = &pushed.span.origin
+        else { panic!("expected a pushed origin, found {:?}", pushed.span.origin); };
+    assert_eq!((by.origin.clone(), by.line, by.column, *tick,), (Origin::file("main.push"), 2, 1, 2,));
+    assert_eq!(pushed.span.to_string(), "1:1 (pushed by main.push:2 at tick 2)");
+}
+
+#[test]
+fn strings_pushed_by_pushed_strings_point_back_to_the_source() {
+    let mut e = Executor::new();
+    e.push_exprs(parser::parse("push queue \"push queue \\\"+ 2 2\\\"\"").unwrap());
+    e.tick();
+    e.tick();
+    let pushed = e.get_expr(1).unwrap();
+    let Origin::Pushed { by, tick } = &pushed.span.origin
+        else { panic!("expected a pushed origin, found {:?}", pushed.span.origin); };
+    assert_eq!((&by.origin, by.line, *tick,), (&Origin::Unknown, 1, 2,));
+    assert_eq!(pushed.span.to_string(), "1:1 (pushed by line 1 at tick 2)");
+}
+
+#[test]
+fn runtime_errors_in_pushed_strings_say_where_they_were_pushed() {
+    let mut e = Executor::new();
+    e.push_exprs(parser::parse("+ 0 0\npush queue \"- 1 \\\"a\\\"\"").unwrap());
+    e.tick();
+    e.tick();
+    let pushed = e.get_expr(1).unwrap().clone();
+    let Value::Error(err) = pushed.execute(&mut e)
+        else { panic!("expected the pushed expression to fail"); };
+    let expr = err.expr.unwrap();
+    assert_eq!(format!("{} at {}", err.kind, expr.span), "type_mismatch at 1:1 (pushed by line 2 at tick 2)");
+}
+
+
+#[test]
+fn every_failing_line_gets_a_diagnostic() {
+    assert_eq!(errors("+ 1 ?\nprint 1\nprint ?\n+\n    1 ?\nprint 2"), [(1, 5,), (3, 7,), (5, 7,)]);
+}
+
+#[test]
+fn recovery_skips_the_rest_of_a_block_comment() {
+    assert_eq!(errors("+ 1 ? /* a comment\nthat goes on ?\n*/\nprint ?"), [(1, 5,), (4, 7,)]);
+    assert_eq!(errors("+ 1 ? /* outer /* inner */\nstill ?\n*/ print ?\nprint ?"), [(1, 5,), (4, 7,)]);
+    assert_eq!(errors("+ 1 \"/*\" ?\nprint ?"), [(1, 10,), (2, 7,)]);
+    assert_eq!(errors("+ 1 ? // /*\nprint ?"), [(1, 5,), (2, 7,)]);
+}
+
+#[test]
+fn diagnostics_render_in_every_format() {
+    let error = parser::parse_with_origin("print 1\n+ 1 ?", Origin::file("main.push")).unwrap_err();
+    let plain = error.render(Render::Plain);
+    assert!(plain.starts_with("Failed to parse line 2 of main.push\n+ 1 ?\n    ^\nExpected "), "{}", plain);
+    assert!(plain.ends_with('\n'));
+
+    let colored = error.render(Render::Colored);
+    assert!(colored.contains("\x1b["));
+    let stripped = colored.split("\x1b[").enumerate()
+        .map(|(i, part,)| if (i == 0) { part } else { &part[(part.find('m').unwrap() + 1)..] })
+        .collect::<String>();
+    assert_eq!(stripped.replace(" Failed to parse line 2 of main.push \n", "Failed to parse line 2 of main.push\n"), plain);
+
+    let json = serde_json::from_str::<serde_json::Value>(&error.render(Render::Json)).unwrap();
+    let errors = json["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["origin"], "main.push");
+    assert_eq!(errors[0]["line"], 2);
+    assert_eq!(errors[0]["column"], 5);
+    assert_eq!(errors[0]["source"], "+ 1 ?");
+    assert!(! errors[0]["expected"].as_array().unwrap().is_empty());
+}
@@ -0,0 +1,92 @@
+use sisyphus::exec::{ Capture, Executor, Limits, PARSE };
+use sisyphus::parser;
+use sisyphus::span::Origin;
+use std::collections::HashSet;
+use std::fs;
+use std::time::Duration;
+
+
+fn executor(script : &str) -> Executor {
+    let mut e = Executor::new().with_output(Capture::new());
+    e.push_exprs(parser::parse(script).unwrap());
+    e
+}
+
+
+#[test]
+fn counts_every_expression() {
+    let mut e = executor("print + 1 2\npush queue \"print 3\"");
+    e.start_profile();
+    e.run(Limits::unlimited());
+    let profile = e.stop_profile().unwrap();
+
+    assert_eq!(profile.ticks, 3);
+    let count = |kind : &str| profile.kinds.get(kind).map_or(0, |stats| stats.count);
+    assert_eq!([count("print"), count("+"), count("push"), count("literal"), count(PARSE)], [2, 1, 1, 5, 1]);
+    assert_eq!(profile.kinds.len(), 5);
+    for stats in profile.kinds.values() { assert!(stats.own <= stats.total); }
+
+    // print 3 was pushed by line 2.
+    assert_eq!(profile.lines["line 1"].ticks, 1);
+    assert_eq!(profile.lines["line 2"].ticks, 2);
+    assert_eq!(profile.lines["line 1"].parse, Duration::ZERO);
+    assert_eq!(profile.lines["line 2"].parse, profile.kinds[PARSE].total);
+    assert_eq!(profile.lines.values().map(|line| line.time).sum::<Duration>(), profile.time);
+
+    let stacks = profile.stacks.keys().map(String::as_str).collect::<HashSet<_>>();
+    assert_eq!(stacks, HashSet::from([
+        "line 1;print", "line 1;print;+", "line 1;print;+;literal",
+        "line 2;push", "line 2;push;literal", "line 2;push;(parse)",
+        "line 2;print", "line 2;print;literal"
+    ]));
+    let own = profile.kinds.values().map(|stats| stats.own).sum::<Duration>();
+    assert_eq!(profile.stacks.values().sum::<Duration>(), own);
+}
+
+#[test]
+fn lines_come_from_the_source() {
+    let source = fs::read_to_string("samples/sqrt.push").unwrap();
+    let mut e = Executor::new().with_output(Capture::new());
+    e.push_exprs(parser::parse_with_origin(&source, Origin::file("samples/sqrt.push")).unwrap());
+    e.start_profile();
+    e.run(Limits::new(200));
+    let profile = e.profile().unwrap();
+
+    assert_eq!(profile.ticks, e.ticks());
+    assert_eq!(profile.lines.values().map(|line| line.ticks).sum::<u64>(), e.ticks());
+    assert!(profile.lines.keys().all(|line| line.starts_with("samples/sqrt.push:")), "{:?}", profile.lines.keys());
+    assert!(profile.kinds[PARSE].count > 0);
+
+    let table = profile.to_string();
+    assert!(table.starts_with(&format!("{} ticks in ", e.ticks())), "{}", table);
+    assert!(table.contains("(parse)"), "{}", table);
+    assert!(table.contains("samples/sqrt.push:1 "), "{}", table);
+
+    let mut collapsed = Vec::new();
+    profile.write_collapsed(&mut collapsed).unwrap();
+    let collapsed = String::from_utf8(collapsed).unwrap();
+    assert_eq!(collapsed.lines().count(), profile.stacks.len());
+    for line in collapsed.lines() {
+        let (stack, nanos,) = line.rsplit_once(' ').unwrap();
+        assert!(stack.starts_with("samples/sqrt.push:"), "{}", line);
+        assert_eq!(profile.stacks[stack].as_nanos(), nanos.parse::<u128>().unwrap());
+    }
+}
+
+#[test]
+fn only_profiles_when_asked() {
+    let mut e = executor("print 1");
+    assert!(e.profile().is_none());
+    e.run(Limits::unlimited());
+    assert!(e.stop_profile().is_none());
+
+    let mut e = executor("print 1\nprint 2");
+    e.start_profile();
+    e.tick();
+    e.evaluate(&parser::parse("len queue").unwrap().remove(0));
+    e.start_profile();
+    e.tick();
+    let profile = e.stop_profile().unwrap();
+    assert_eq!(profile.ticks, 1);
+    assert_eq!(profile.kinds.keys().copied().collect::<HashSet<_>>(), HashSet::from(["print", "literal"]));
+}
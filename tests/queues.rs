@@ -0,0 +1,125 @@
+mod common;
+use common::{ eval, error };
+use sisyphus::exec::{ Capture, Executor, Limits, Outcome, Trace, Value, ErrorKind };
+use sisyphus::parser;
+
+
+fn run(script : &str) -> (Outcome, String,) {
+    let output = Capture::new();
+    let mut e = Executor::new().with_output(output.clone());
+    e.push_exprs(parser::parse(script).unwrap());
+    let outcome = e.run(Limits::new(1000).with_stop_on_error()).outcome;
+    (outcome, output.contents(),)
+}
+
+fn data(e : &Executor) -> Vec<String> {
+    e.named_queue("data").unwrap().iter().map(|expr| expr.to_string()).collect()
+}
+
+
+#[test]
+fn named_queues_use_the_same_builtins() {
+    let mut e = Executor::new();
+    assert_eq!(eval(&mut e, "mkqueue \"data\""), Value::NamedQueue("data".to_string()));
+    assert_eq!(eval(&mut e, "pushes (push (named \"data\") \"+ 1 2\") (push (range 0 0) 4)"), Value::NamedQueue("data".to_string()));
+    eval(&mut e, "insert (named \"data\") 0 \"print 1\"");
+    eval(&mut e, "set (named \"data\") 2 5");
+    assert_eq!(data(&e), ["print 1", "+ 1 2", "5"]);
+    assert_eq!(eval(&mut e, "get (named \"data\") 1"), Value::String("+ 1 2".to_string()));
+    assert_eq!(eval(&mut e, "gets (named \"data\") 1 3"), Value::Array(vec![Value::String("+ 1 2".to_string()), Value::String("5".to_string())]));
+    assert_eq!(eval(&mut e, "len (named \"data\")"), Value::Int(3));
+    assert_eq!(eval(&mut e, "str (named \"data\")"), Value::String("exprqueue(data)".to_string()));
+    // The main queue is untouched.
+    assert_eq!(e.len_exprs(), 1);
+
+    assert_eq!(error(&mut e, "mkqueue \"data\""), ErrorKind::Queue);
+    assert_eq!(error(&mut e, "mkqueue \"two words\""), ErrorKind::Queue);
+    assert_eq!(error(&mut e, "mkqueue 1"), ErrorKind::TypeMismatch);
+    assert_eq!(error(&mut e, "push (named \"missing\") 1"), ErrorKind::Queue);
+    assert_eq!(error(&mut e, "get (named \"data\") 3"), ErrorKind::OutOfRange);
+    assert_eq!(error(&mut e, "set (named \"data\") 3 1"), ErrorKind::OutOfRange);
+    assert_eq!(error(&mut e, "insert (named \"data\") 4 1"), ErrorKind::OutOfRange);
+    assert_eq!(error(&mut e, "+ (named \"data\") 1"), ErrorKind::TypeMismatch);
+    assert_eq!(data(&e), ["print 1", "+ 1 2", "5"]);
+}
+
+#[test]
+fn transfers_between_queues() {
+    let mut e = Executor::new();
+    e.push_exprs(parser::parse("print 1\nprint 2\nprint 3").unwrap());
+    eval(&mut e, "mkqueue \"data\"");
+    assert_eq!(eval(&mut e, "transfer queue (named \"data\") 2"), Value::NamedQueue("data".to_string()));
+    assert_eq!(data(&e), ["print 1", "print 2"]);
+    assert_eq!(e.get_exprs(1, e.len_exprs()).unwrap().iter().map(|expr| expr.to_string()).collect::<Vec<_>>(), ["print 3"]);
+    assert_eq!(eval(&mut e, "transfer (named \"data\") queue 1"), Value::ExprQueue);
+    assert_eq!(data(&e), ["print 2"]);
+    assert_eq!(e.len_exprs(), 3);
+
+    assert_eq!(error(&mut e, "transfer (named \"data\") queue 2"), ErrorKind::OutOfRange);
+    assert_eq!(error(&mut e, "transfer queue (named \"missing\") 1"), ErrorKind::Queue);
+    assert_eq!(error(&mut e, "transfer queue 1 1"), ErrorKind::TypeMismatch);
+    assert_eq!(data(&e), ["print 2"]);
+    assert_eq!(e.len_exprs(), 3);
+}
+
+#[test]
+fn only_scheduled_queues_are_executed() {
+    let (outcome, output,) = run("push (push (mkqueue \"a\") \"print 10\") \"print 11\"\nprint 1\nprint 2");
+    assert_eq!((outcome, output.as_str(),), (Outcome::Completed, "1\n2\n"));
+
+    // Queues take turns, skipping the empty ones.
+    let (outcome, output,) = run("schedule (push (push (mkqueue \"a\") \"print 10\") \"print 11\") true\nprint 1\nprint 2\nprint 3");
+    assert_eq!((outcome, output.as_str(),), (Outcome::Completed, "10\n1\n11\n2\n3\n"));
+
+    let (outcome, output,) = run("schedule (mkqueue \"a\") true\nschedule (mkqueue \"b\") true\npushes (named \"a\") (push (push (push (range 0 0) \"print 10\") \"print 11\") \"print 12\")\npush (named \"b\") \"schedule (named \\\"a\\\") false\"\nprint 1\nprint 2");
+    assert_eq!((outcome, output.as_str(),), (Outcome::Completed, "10\n11\n1\n2\n"));
+
+    let (outcome, _,) = run("schedule queue true");
+    assert!(matches!(outcome, Outcome::Failed(err) if (err.kind == ErrorKind::TypeMismatch)));
+}
+
+#[test]
+fn snapshots_and_traces_keep_named_queues() {
+    let script = "schedule (push (push (mkqueue \"a\") \"print 10\") \"push (named \\\"b\\\") 1\") true\nmkqueue \"b\"\nprint 1\nprint 2\ntransfer (named \"b\") queue 1";
+    let recorded = Capture::new();
+    let mut e = Executor::new().with_output(Capture::new());
+    e.start_trace(recorded.clone());
+    e.push_exprs(parser::parse(script).unwrap());
+    let mut snapshots = Vec::new();
+    while (e.tick()) {
+        let mut snapshot = Vec::new();
+        e.save(&mut snapshot).unwrap();
+        snapshots.push(snapshot);
+    }
+    e.stop_trace().unwrap();
+    let trace = Trace::read(recorded.contents().as_bytes()).unwrap();
+    assert_eq!(trace.ticks().map(|(tick, _, _,)| tick).collect::<Vec<_>>(), (1..=e.ticks()).collect::<Vec<_>>());
+
+    for (i, snapshot,) in snapshots.iter().enumerate() {
+        let tick = i as u64 + 1;
+        let mut loaded = Executor::new();
+        loaded.load(snapshot.as_slice()).unwrap();
+        let replayed = trace.executor_at(tick).unwrap();
+        for e in [&loaded, &replayed] {
+            let mut saved = Vec::new();
+            e.save(&mut saved).unwrap();
+            assert_eq!(String::from_utf8(saved).unwrap(), String::from_utf8(snapshot.clone()).unwrap(), "at tick {}", tick);
+        }
+    }
+}
+
+#[test]
+fn evaluating_leaves_named_queues_alone() {
+    let mut e = Executor::new();
+    eval(&mut e, "push (mkqueue \"data\") 1");
+    e.evaluate(&parser::parse("push (named \"data\") 2").unwrap().remove(0));
+    assert_eq!(data(&e), ["1"]);
+}
+
+#[test]
+fn changing_named_queues_is_not_a_cycle() {
+    let mut e = Executor::new();
+    e.push_exprs(parser::parse("mkqueue \"data\"\n+ (len (push (named \"data\") 1)) (len (push queue (get queue 0)))").unwrap());
+    assert_eq!(e.run(Limits::new(100).with_cycle_detection()).outcome, Outcome::BudgetExhausted);
+    assert_eq!(e.named_queue("data").unwrap().len(), 99);
+}
@@ -0,0 +1,121 @@
+use sisyphus::exec::{ Executor, Rope, Summary };
+use sisyphus::parser;
+use proptest::prelude::*;
+use std::collections::VecDeque;
+
+
+#[derive(Debug, Clone)]
+enum Op {
+    Insert(usize, u32),
+    Set(usize, u32),
+    Remove(usize),
+    PushBack(u32),
+    PopFront,
+    Clone
+}
+
+/// Every value, in order, to check that summaries are kept up to date.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Values(Vec<u32>);
+
+impl Summary<u32> for Values {
+    fn join(left : &Self, value : &u32, right : &Self) -> Self {
+        Self([&left.0[..], &[*value], &right.0[..]].concat())
+    }
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (any::<usize>(), any::<u32>()).prop_map(|(i, v,)| Op::Insert(i, v)),
+        (any::<usize>(), any::<u32>()).prop_map(|(i, v,)| Op::Set(i, v)),
+        any::<usize>().prop_map(Op::Remove),
+        any::<u32>().prop_map(Op::PushBack),
+        Just(Op::PopFront),
+        Just(Op::Clone)
+    ]
+}
+
+
+proptest! {
+
+    #[test]
+    fn rope_behaves_like_a_vec_deque(ops in prop::collection::vec(op(), 0..200)) {
+        let mut rope    = Rope::new();
+        let mut summed  = Rope::<u32, Values>::new();
+        let mut model   = VecDeque::new();
+        let mut clones  = Vec::new();
+        for op in ops {
+            match (op) {
+                Op::Insert(i, v) => {
+                    let i = i % (model.len() + 1);
+                    rope.insert(i, v);
+                    summed.insert(i, v);
+                    model.insert(i, v);
+                },
+                Op::Set(i, v)    => if (! model.is_empty()) {
+                    let i = i % model.len();
+                    rope[i]  = v;
+                    prop_assert_eq!(summed.set(i, v), model[i]);
+                    model[i] = v;
+                },
+                Op::Remove(i)    => {
+                    let i = i % (model.len() + 1);
+                    prop_assert_eq!(summed.remove(i), model.get(i).copied());
+                    prop_assert_eq!(rope.remove(i), model.remove(i));
+                },
+                Op::PushBack(v)  => {
+                    rope.push_back(v);
+                    summed.push_back(v);
+                    model.push_back(v);
+                },
+                Op::PopFront     => {
+                    prop_assert_eq!(summed.pop_front(), model.front().copied());
+                    prop_assert_eq!(rope.pop_front(), model.pop_front());
+                },
+                Op::Clone        => { clones.push((rope.clone(), model.clone(),)); }
+            }
+            prop_assert_eq!(rope.len(), model.len());
+            prop_assert_eq!(&summed.summary().0, &model.iter().copied().collect::<Vec<_>>());
+        }
+        prop_assert!(rope.iter().eq(model.iter()));
+        for (rope, model,) in clones {
+            prop_assert!(rope.iter().eq(model.iter()));
+        }
+    }
+
+    #[test]
+    fn range_visits_exactly_the_range(len in 0..100usize, a in any::<usize>(), b in any::<usize>()) {
+        let rope  = (0..len).collect::<Rope<_>>();
+        let start = a % (len + 1);
+        let end   = start + b % (len - start + 1);
+        prop_assert!(rope.range(start..end).eq((start..end).collect::<Vec<_>>().iter()));
+        prop_assert_eq!(rope.range(start..end).len(), end - start);
+    }
+
+}
+
+
+#[test]
+fn clones_keep_their_own_values() {
+    let mut a = (0..1000).collect::<Rope<_>>();
+    let b     = a.clone();
+    a[500] = -1;
+    a.insert(0, -2);
+    assert_eq!(b[500], 500);
+    assert_eq!(b.len(), 1000);
+    assert_eq!(a[501], -1);
+    assert_eq!(a.front(), Some(&-2));
+}
+
+#[test]
+fn get_exprs_spans_the_latest_expression_and_the_queue() {
+    let mut e = Executor::new();
+    e.push_exprs(parser::parse("1\n2\n3").unwrap());
+    let exprs = |e : &Executor, i0, i1| e.get_exprs(i0, i1).map(|exprs| exprs.iter().map(|expr| expr.to_string()).collect::<Vec<_>>());
+    assert_eq!(exprs(&e, 0, 4).unwrap(), ["false", "1", "2", "3"]);
+    assert_eq!(exprs(&e, 2, 4).unwrap(), ["2", "3"]);
+    assert_eq!(exprs(&e, 0, 1).unwrap(), ["false"]);
+    assert_eq!(exprs(&e, 3, 3).unwrap(), Vec::<String>::new());
+    assert_eq!(exprs(&e, 3, 1).unwrap(), Vec::<String>::new());
+    assert_eq!(exprs(&e, 0, 5), None);
+}
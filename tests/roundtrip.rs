@@ -0,0 +1,123 @@
+use sisyphus::builtin::Builtin;
+use sisyphus::expr::{ Expr, ExprKind, Lit };
+use sisyphus::parser;
+use f128::f128;
+use proptest::prelude::*;
+use proptest::{ collection, sample };
+use std::fs;
+
+
+fn lit() -> impl Strategy<Value = Lit> {
+    prop_oneof![
+        any::<bool>().prop_map(Lit::Bool),
+        any::<i128>().prop_map(Lit::Int),
+        float().prop_map(Lit::Float),
+        any::<String>().prop_map(Lit::String),
+        Just(Lit::ExprQueue)
+    ]
+}
+
+// NaN never equals itself, so it can't take part in an equality check.
+fn float() -> impl Strategy<Value = f128> {
+    prop_oneof![
+        any::<f64>().prop_filter("not NaN", |v| ! v.is_nan()).prop_map(f128::from),
+        (any::<i128>(), any::<i128>().prop_filter("not zero", |v| *v != 0))
+            .prop_map(|(n, d,)| f128::from(n) / f128::from(d))
+    ]
+}
+
+fn expr() -> impl Strategy<Value = Expr> {
+    let leaf = lit().prop_map(|l| Expr::from(ExprKind::Lit(l)));
+    leaf.prop_recursive(4, 48, 3, |inner| {
+        sample::select(Builtin::ALL)
+            .prop_flat_map(move |b| {
+                let rest = if (b.variadic()) { 3 } else { 0 };
+                collection::vec(inner.clone(), b.arity()..=(b.arity() + rest)).prop_map(move |a| ExprKind::Builtin(b, a))
+            })
+            .prop_map(Expr::from)
+    })
+}
+
+
+proptest! {
+
+    #[test]
+    fn display_parses_back_to_the_same_expr(expr in expr()) {
+        let text = expr.to_string();
+        let parsed = parser::parse(&text);
+        prop_assert!(parsed.is_ok(), "{:?} failed to parse:\n{}", text, parsed.unwrap_err());
+        prop_assert_eq!(parsed.unwrap(), vec![expr]);
+    }
+
+}
+
+
+#[test]
+fn special_floats_parse_back_to_the_same_class() {
+    for (value, text,) in [
+        (f128::NAN,          "nan"),
+        (f128::INFINITY,     "inf"),
+        (f128::NEG_INFINITY, "-inf")
+    ] {
+        let expr = Expr::from(ExprKind::Lit(Lit::Float(value)));
+        assert_eq!(expr.to_string(), text);
+        let parsed = parser::parse(text).unwrap();
+        let [Expr { kind : ExprKind::Lit(Lit::Float(float)), .. }] = parsed.as_slice()
+            else { panic!("{} parsed as {:?}", text, parsed); };
+        // NaN is the only value that isn't comparable with itself.
+        let same_class = match (text) {
+            "nan" => float.partial_cmp(float).is_none(),
+            _     => *float == value
+        };
+        assert!(same_class, "{} parsed as {:?}", text, parsed);
+    }
+}
+
+#[test]
+fn display_keeps_keywords_apart() {
+    for (text, builtin,) in [
+        ("len queue",  Builtin::Len),
+        ("% 7 3",      Builtin::Rem),
+        ("- -1 -2.5",  Builtin::Sub),
+        (">= 1 2",     Builtin::GreaterEquals)
+    ] {
+        let exprs = parser::parse(text).unwrap();
+        assert_eq!(exprs.len(), 1);
+        assert!(matches!(exprs[0].kind, ExprKind::Builtin(b, _) if b == builtin), "{} parsed as {:?}", text, exprs[0].kind);
+        assert_eq!(exprs[0].to_string(), text);
+    }
+}
+
+#[test]
+fn display_closes_variadic_calls() {
+    for text in [
+        "call \"f\" 1 2",
+        "call \"f\"",
+        "+ (call \"f\" 1) 2",
+        "+ (! call \"f\" 1) 2",
+        "call \"f\" (call \"g\") call \"h\" 1"
+    ] {
+        let exprs = parser::parse(text).unwrap();
+        assert_eq!(exprs.len(), 1);
+        assert_eq!(exprs[0].to_string(), text);
+    }
+}
+
+#[test]
+fn display_escapes_strings_the_parser_accepts() {
+    let lit = Expr::from(ExprKind::Lit(Lit::String("\"q\" \\ \n\r\t\0 \u{7} \u{1b}[0m é `'".to_string())));
+    assert_eq!(lit.to_string(), r#""\"q\" \\ \n\r\t\0 \u{7} \u{1b}[0m é `'""#);
+    assert_eq!(parser::parse(&lit.to_string()).unwrap(), vec![lit]);
+}
+
+#[test]
+fn samples_round_trip() {
+    for entry in fs::read_dir("samples").unwrap() {
+        let path   = entry.unwrap().path();
+        let source = fs::read_to_string(&path).unwrap();
+        let exprs  = parser::parse(&source).unwrap();
+        for expr in exprs {
+            assert_eq!(parser::parse(&expr.to_string()).unwrap(), vec![expr], "in {}", path.display());
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use sisyphus::exec::{ Executor, Limits, Outcome, ErrorKind };
+use sisyphus::parser;
+use std::time::Duration;
+
+
+fn executor(script : &str) -> Executor {
+    let mut executor = Executor::new();
+    executor.push_exprs(parser::parse(script).unwrap());
+    executor
+}
+
+// Pushes a copy of itself every tick, forever.
+const FOREVER : &str = "push queue (get queue 0)";
+
+// Pushes two copies of itself every tick, so the queue keeps growing.
+const GROWING : &str = "pushes queue (* (push (range 0 0) (get queue 0)) 2)";
+
+
+#[test]
+fn completes() {
+    let mut e = executor("+ 1 2\npush queue \"+ 3 4\"\n- 1 2");
+    let report = e.run(Limits::new(100));
+    assert_eq!(report.outcome, Outcome::Completed);
+    assert_eq!(report.ticks, 4);
+    assert_eq!(report.peak_queue, 3);
+}
+
+#[test]
+fn exhausts_the_budget_and_can_continue() {
+    let mut e = executor(FOREVER);
+    let report = e.run(Limits::new(1000));
+    assert_eq!(report.outcome, Outcome::BudgetExhausted);
+    assert_eq!(report.ticks, 1000);
+    assert_eq!(report.peak_queue, 1);
+
+    let report = e.run(Limits::new(10));
+    assert_eq!(report.outcome, Outcome::BudgetExhausted);
+    assert_eq!(report.ticks, 10);
+    assert_eq!(e.ticks(), 1010);
+}
+
+#[test]
+fn exceeds_the_queue_limit() {
+    let mut e = executor(GROWING);
+    let report = e.run(Limits::unlimited().with_queue(64));
+    assert_eq!(report.outcome, Outcome::QueueLimitExceeded);
+    assert_eq!(report.peak_queue, 65);
+    assert_eq!(report.ticks, 64);
+}
+
+#[test]
+fn exceeds_the_time_limit() {
+    let mut e = executor(FOREVER);
+    let report = e.run(Limits::unlimited().with_time(Duration::from_millis(20)));
+    assert_eq!(report.outcome, Outcome::TimeLimitExceeded);
+    assert!(report.elapsed >= Duration::from_millis(20));
+    assert!(report.ticks > 0);
+}
+
+#[test]
+fn halts_with_a_code() {
+    let mut e = executor("+ 1 2\nexit 3\n+ 4 5");
+    let report = e.run(Limits::new(100));
+    assert_eq!(report.outcome, Outcome::Halted(3));
+    assert_eq!(report.ticks, 2);
+    assert_eq!(e.len_exprs(), 2);
+    assert!(! e.tick());
+    assert_eq!(e.run(Limits::new(100)).outcome, Outcome::Halted(3));
+}
+
+#[test]
+fn fails_on_an_error() {
+    let mut e = executor("+ 1 2\nget (range 0 2) 5\n+ 4 5");
+    let report = e.run(Limits::new(100).with_stop_on_error());
+    let Outcome::Failed(err) = report.outcome
+        else { panic!("expected a failure, found {:?}", report.outcome); };
+    assert_eq!(err.kind, ErrorKind::OutOfRange);
+    assert_eq!(report.ticks, 2);
+}
+
+#[test]
+fn carries_on_after_an_error_by_default() {
+    let mut e = executor("+ 1 2\nget (range 0 2) 5\n+ 4 5");
+    let report = e.run(Limits::new(100));
+    assert_eq!((report.outcome, report.ticks,), (Outcome::Completed, 3,));
+}
+
+#[test]
+fn exit_codes_must_fit_in_a_byte() {
+    let mut e = executor("exit 256\nexit -1\nexit 255");
+    let report = e.run(Limits::new(100).with_stop_on_error());
+    let Outcome::Failed(err) = report.outcome
+        else { panic!("expected a failure, found {:?}", report.outcome); };
+    assert_eq!(err.kind, ErrorKind::OutOfRange);
+    assert_eq!(e.halted(), None);
+    // exit -1 fails too, but the run carries on to exit 255.
+    assert_eq!(e.run(Limits::new(100)).outcome, Outcome::Halted(255));
+}
+
+#[test]
+fn traces_every_expression() {
+    let mut e = executor("+ 1 2\npush queue \"+ 3 4\"");
+    let mut traced = Vec::new();
+    e.run_tracing(Limits::unlimited(), |tick, expr| traced.push((tick, expr.to_string(),)));
+    assert_eq!(traced, [
+        (1, "+ 1 2".to_string(),),
+        (2, "push queue \"+ 3 4\"".to_string(),),
+        (3, "+ 3 4".to_string(),)
+    ]);
+}
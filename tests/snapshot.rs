@@ -0,0 +1,111 @@
+use sisyphus::exec::{ Capture, Executor, Limits, Outcome, SnapshotError };
+use sisyphus::parser;
+use std::fs;
+
+
+fn executor(output : &Capture) -> Executor {
+    Executor::new().with_output(output.clone())
+}
+
+fn sample(name : &str) -> Executor {
+    let mut e = Executor::new().with_output(Capture::new());
+    e.push_exprs(parser::parse(&fs::read_to_string(format!("samples/{}", name)).unwrap()).unwrap());
+    e
+}
+
+fn snapshot(e : &Executor) -> Vec<u8> {
+    let mut snapshot = Vec::new();
+    e.save(&mut snapshot).unwrap();
+    snapshot
+}
+
+
+#[test]
+fn resuming_gives_the_same_output() {
+    for name in ["big_list.push", "prime_main.push", "sqrt.push", "sqrt.push.lesspain"] {
+        let whole = Capture::new();
+        let mut e = executor(&whole);
+        e.push_exprs(parser::parse(&fs::read_to_string(format!("samples/{}", name)).unwrap()).unwrap());
+        let outcome = e.run(Limits::new(5000)).outcome;
+
+        let pieces   = Capture::new();
+        let mut part = executor(&pieces);
+        part.push_exprs(parser::parse(&fs::read_to_string(format!("samples/{}", name)).unwrap()).unwrap());
+        let mut ticks = 0;
+        let resumed_outcome = loop {
+            let report = part.run(Limits::new(137.min(5000 - ticks)));
+            ticks += report.ticks;
+            if (report.outcome != Outcome::BudgetExhausted || ticks == 5000) { break report.outcome; }
+            let saved = snapshot(&part);
+            part = executor(&pieces);
+            part.load(saved.as_slice()).unwrap();
+        };
+
+        assert_eq!(resumed_outcome, outcome, "in {}", name);
+        assert_eq!(pieces.contents(), whole.contents(), "in {}", name);
+        assert_eq!(snapshot(&part), snapshot(&e), "in {}", name);
+    }
+}
+
+#[test]
+fn snapshots_keep_the_state() {
+    let mut e = sample("prime_main.push");
+    e.run(Limits::new(321));
+    let saved = snapshot(&e);
+
+    let mut loaded = Executor::new();
+    loaded.load(saved.as_slice()).unwrap();
+    assert_eq!(loaded.ticks(), 321);
+    assert_eq!(loaded.len_exprs(), e.len_exprs());
+    for i in 0..e.len_exprs() {
+        assert_eq!(loaded.get_expr(i), e.get_expr(i));
+    }
+    assert_eq!(snapshot(&loaded), saved);
+}
+
+#[test]
+fn snapshots_keep_halting() {
+    let mut e = Executor::new();
+    e.push_exprs(parser::parse("exit 4\n+ 1 2").unwrap());
+    e.run(Limits::unlimited());
+    let saved = String::from_utf8(snapshot(&e)).unwrap();
+    assert!(saved.contains("\nhalted 4\n"), "{}", saved);
+
+    let mut loaded = Executor::new();
+    loaded.load(saved.as_bytes()).unwrap();
+    assert_eq!(loaded.run(Limits::unlimited()).outcome, Outcome::Halted(4));
+}
+
+#[test]
+fn bad_snapshots_are_rejected() {
+    let mut e = Executor::new();
+    e.push_exprs(parser::parse("+ 1 2").unwrap());
+    for (text, line,) in [
+        ("",                                                        1),
+        ("not a snapshot\n",                                        1),
+        ("sisyphus-snapshot 1\nticks x\nlatest false\n",            2),
+        ("sisyphus-snapshot 1\nticks 1\nlatest false\nqueued (\n",  4),
+        ("sisyphus-snapshot 1\nticks 1\nlatest 1 2\n",              3),
+        ("sisyphus-snapshot 1\nticks 1\nlatest 1\nspeed 3\n",       4),
+        ("sisyphus-snapshot 1\nlatest false\n",                     2)
+    ] {
+        match (e.load(text.as_bytes())) {
+            Err(SnapshotError::Format { line : l, .. }) => assert_eq!(l, line, "{:?}", text),
+            result                                      => panic!("{:?} loaded as {:?}", text, result)
+        }
+    }
+    assert_eq!(e.len_exprs(), 2);
+    assert_eq!(e.ticks(), 0);
+}
+
+#[test]
+fn channels_and_spawned_scripts_cant_be_saved() {
+    for script in ["send (channel \"c\") 1", "channel \"c\"", "spawn \"print 1\"", "recv (channel \"c\")"] {
+        let mut e = executor(&Capture::new());
+        e.push_exprs(parser::parse(script).unwrap());
+        e.run(Limits::new(1));
+        let mut saved = Vec::new();
+        assert!(e.save(&mut saved).is_err(), "{}", script);
+        assert!(saved.is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+use sisyphus::builtin::Builtin;
+use std::collections::BTreeMap;
+
+
+const SPEC : &str = include_str!("../sisyphus-spec/src/lib.rs");
+
+// Documented in the spec, but written as literals rather than builtins.
+const LITERALS : &[&str] = &["queue"];
+
+// Documented in the spec, but not implemented yet.
+const UNIMPLEMENTED : &[&str] = &[];
+
+
+struct Documented {
+    params : Vec<String>,
+    doc    : String
+}
+
+/// Every `pub fn` of the spec, keyed by its alias if it has one.
+fn documented() -> BTreeMap<String, Documented> {
+    let mut builtins = BTreeMap::new();
+    let mut doc      = String::new();
+    let mut alias    = None;
+    for line in SPEC.lines() {
+        let line = line.trim();
+        if let Some(text) = line.strip_prefix("///") {
+            doc += text;
+            doc += "\n";
+        } else if let Some(rest) = line.strip_prefix("#[doc(alias = \"") {
+            alias = Some(rest.trim_end_matches("\")]").to_string());
+        } else if let Some(rest) = line.strip_prefix("pub fn ") {
+            let name   = rest[..rest.find(['<', '(']).unwrap()].trim_start_matches("r#");
+            let params = &rest[(rest.find('(').unwrap() + 1)..rest.find(')').unwrap()];
+            let params = params.split(',')
+                .filter(|param| ! param.trim().is_empty())
+                .map(|param| param.split(':').next().unwrap().trim().to_string())
+                .collect();
+            let keyword = alias.take().unwrap_or_else(|| name.to_string());
+            builtins.insert(keyword, Documented { params, doc : doc.clone() });
+            doc.clear();
+        } else {
+            doc.clear();
+            alias = None;
+        }
+    }
+    builtins
+}
+
+
+#[test]
+fn every_builtin_is_documented_by_the_spec() {
+    let documented = documented();
+    for &builtin in Builtin::ALL {
+        let Some(spec) = documented.get(builtin.keyword())
+            else { panic!("{} is not in sisyphus-spec", builtin.keyword()); };
+        assert_eq!(builtin.params(), spec.params, "parameters of {}", builtin.keyword());
+        assert_eq!(builtin.doc(), spec.doc, "documentation of {}", builtin.keyword());
+    }
+}
+
+#[test]
+fn every_documented_builtin_is_implemented() {
+    for keyword in documented().keys() {
+        if (LITERALS.contains(&keyword.as_str())) { continue; }
+        let implemented = Builtin::from_keyword(keyword).is_some();
+        if (UNIMPLEMENTED.contains(&keyword.as_str())) {
+            assert!(! implemented, "{} is implemented, so it should leave UNIMPLEMENTED", keyword);
+        } else {
+            assert!(implemented, "{} is in sisyphus-spec but not implemented", keyword);
+        }
+    }
+}
+
+#[test]
+fn keywords_are_unique() {
+    for (i, a,) in Builtin::ALL.iter().enumerate() {
+        assert_eq!(Builtin::from_keyword(a.keyword()), Some(*a));
+        assert!(Builtin::ALL[(i + 1)..].iter().all(|b| a.keyword() != b.keyword()));
+    }
+}
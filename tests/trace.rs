@@ -0,0 +1,115 @@
+use sisyphus::exec::{ Capture, Executor, Limits, Trace, TraceError, TraceEvent };
+use sisyphus::parser;
+use std::fs;
+
+
+fn sample(name : &str) -> Executor {
+    let mut e = Executor::new().with_output(Capture::new());
+    e.push_exprs(parser::parse(&fs::read_to_string(format!("samples/{}", name)).unwrap()).unwrap());
+    e
+}
+
+fn queue(e : &Executor) -> Vec<String> {
+    (0..e.len_exprs()).map(|i| e.get_expr(i).unwrap().to_string()).collect()
+}
+
+fn printed(queue : Vec<sisyphus::expr::Expr>) -> Vec<String> {
+    queue.iter().map(|expr| expr.to_string()).collect()
+}
+
+
+#[test]
+fn reconstructs_the_queue_at_every_tick() {
+    for name in ["big_list.push", "prime_main.push", "sqrt.push"] {
+        let recorded = Capture::new();
+        let mut e = sample(name);
+        e.start_trace(recorded.clone());
+        e.run(Limits::new(400));
+        e.stop_trace().unwrap();
+        let trace = Trace::read(recorded.contents().as_bytes()).unwrap();
+
+        let mut replayed = sample(name);
+        for tick in 0..=e.ticks() {
+            assert_eq!(trace.queue_at(tick).map(printed), Some(queue(&replayed)), "at tick {} of {}", tick, name);
+            replayed.tick();
+        }
+        assert_eq!(trace.queue_at(e.ticks() + 1), None);
+    }
+}
+
+#[test]
+fn records_results_and_queue_changes() {
+    let recorded = Capture::new();
+    let mut e = Executor::new().with_output(Capture::new());
+    e.start_trace(recorded.clone());
+    e.push_exprs(parser::parse("push queue \"print 1\"\ninsert queue 1 \"len queue\"\nset queue 1 \"+ 1 2\"\n/ 1 0\n/ 2 0").unwrap());
+    e.run(Limits::unlimited());
+    e.stop_trace().unwrap();
+    let trace = Trace::read(recorded.contents().as_bytes()).unwrap();
+
+    let ticks = trace.ticks().map(|(tick, expr, result,)| (tick, expr.to_string(), result.map(str::to_string))).collect::<Vec<_>>();
+    assert_eq!(ticks[0], (1, "push queue \"print 1\"".to_string(), Some("exprqueue".to_string())));
+    assert_eq!(ticks[2], (3, "len queue".to_string(), Some("5".to_string())));
+    assert_eq!(ticks[4], (5, "+ 1 2".to_string(), Some("3".to_string())));
+    assert!(ticks[5].2.as_deref().unwrap().contains("division_by_zero"), "{:?}", ticks[5]);
+
+    let parse = |text : &str| parser::parse(text).unwrap().remove(0);
+    assert!(trace.events.contains(&TraceEvent::Push(parse("print 1"))));
+    assert!(trace.events.contains(&TraceEvent::Insert(1, parse("len queue"))));
+    assert!(trace.events.contains(&TraceEvent::Set(1, parse("+ 1 2"))));
+    assert_eq!(trace.queue_at(4).map(printed), Some(vec![
+        "set queue 1 \"+ 1 2\"".to_string(), "+ 1 2".to_string(), "/ 2 0".to_string(), "print 1".to_string()
+    ]));
+}
+
+#[test]
+fn inserting_at_0_records_the_latest_expression_at_1() {
+    let recorded = Capture::new();
+    let mut e = Executor::new().with_output(Capture::new());
+    e.start_trace(recorded.clone());
+    e.push_exprs(parser::parse("insert queue 0 \"print 1\"").unwrap());
+    e.run(Limits::new(3));
+    e.stop_trace().unwrap();
+    let trace = Trace::read(recorded.contents().as_bytes()).unwrap();
+
+    let latest = parser::parse("insert queue 0 \"print 1\"").unwrap().remove(0);
+    assert!(trace.events.contains(&TraceEvent::Insert(1, latest)));
+    assert!(! trace.events.iter().any(|event| matches!(event, TraceEvent::Insert(0, _))));
+    assert_eq!(trace.queue_at(3).map(printed), Some(queue(&e)));
+}
+
+#[test]
+fn loading_a_snapshot_resets_the_trace() {
+    let mut saved = Vec::new();
+    let mut e = sample("sqrt.push");
+    e.run(Limits::new(30));
+    e.save(&mut saved).unwrap();
+
+    let recorded = Capture::new();
+    let mut resumed = Executor::new().with_output(Capture::new());
+    resumed.start_trace(recorded.clone());
+    resumed.load(saved.as_slice()).unwrap();
+    resumed.run(Limits::new(10));
+    resumed.stop_trace().unwrap();
+    let trace = Trace::read(recorded.contents().as_bytes()).unwrap();
+
+    assert_eq!(trace.queue_at(10), None);
+    assert_eq!(trace.queue_at(30).map(printed), Some(queue(&e)));
+    e.run(Limits::new(10));
+    assert_eq!(trace.queue_at(40).map(printed), Some(queue(&e)));
+}
+
+#[test]
+fn bad_traces() {
+    let line = |text : &str| match (Trace::read(text.as_bytes())) {
+        Err(TraceError::Format { line, .. }) => line,
+        other                               => panic!("{:?}", other)
+    };
+    assert_eq!(line(""), 1);
+    assert_eq!(line("sisyphus-snapshot 1\n"), 1);
+    assert_eq!(line("sisyphus-trace 1\nreset 0\npop 1\n"), 3);
+    assert_eq!(line("sisyphus-trace 1\nreset many\n"), 2);
+    assert_eq!(line("sisyphus-trace 1\nreset 0\nlatest false\ntick 1 print (\n"), 4);
+    assert_eq!(line("sisyphus-trace 1\nresult 3\n"), 2);
+    assert!(Trace::read("sisyphus-trace 1\n".as_bytes()).unwrap().events.is_empty());
+}
@@ -0,0 +1,104 @@
+mod common;
+use common::{ eval, error };
+use sisyphus::exec::{ Executor, Value, ErrorKind };
+use sisyphus::exec::vfs::{ Access, FileSystem, Jail, Memory };
+use std::fs;
+use tempfile::TempDir;
+
+
+#[test]
+fn memory_files() {
+    let memory = Memory::new();
+    memory.insert("in.txt", "hello");
+    let mut e = Executor::new().with_file_system(memory.clone());
+
+    assert_eq!(eval(&mut e, "fsread \"in.txt\""), Value::String("hello".to_string()));
+    assert_eq!(eval(&mut e, "fswrite \"out.txt\" (+ (fsread \"in.txt\") \"!\")"), Value::Unit);
+    assert_eq!(memory.get("out.txt").unwrap(), b"hello!");
+    assert_eq!(eval(&mut e, "fbwrite \"bytes\" (range 0 3)"), Value::Unit);
+    assert_eq!(memory.get("bytes").unwrap(), [0, 1, 2]);
+    assert_eq!(error(&mut e, "fsread \"missing\""), ErrorKind::Io);
+}
+
+#[test]
+fn memory_rejects_invalid_utf8_as_strings() {
+    let mut memory = Memory::new();
+    memory.insert("bin", [0xff, 0xfe]);
+    assert!(memory.read_to_string("bin").is_err());
+    assert_eq!(memory.read("bin").unwrap(), [0xff, 0xfe]);
+}
+
+#[test]
+fn read_only_access() {
+    let memory = Memory::new();
+    memory.insert("in.txt", "hello");
+    let mut e = Executor::new().with_file_system(memory.clone()).with_file_access(Access::ReadOnly);
+
+    assert_eq!(eval(&mut e, "fsread \"in.txt\""), Value::String("hello".to_string()));
+    assert_eq!(error(&mut e, "fswrite \"in.txt\" \"bye\""), ErrorKind::Io);
+    assert_eq!(error(&mut e, "fbwrite \"in.txt\" (range 0 1)"), ErrorKind::Io);
+    assert_eq!(memory.get("in.txt").unwrap(), b"hello");
+}
+
+#[test]
+fn jail_reads_and_writes_inside_its_root() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("sub/in.txt"), "hello").unwrap();
+    let mut jail = Jail::new(dir.path()).unwrap();
+
+    assert_eq!(jail.read_to_string("sub/in.txt").unwrap(), "hello");
+    assert_eq!(jail.read_to_string("/sub/./in.txt").unwrap(), "hello");
+    jail.write("out.txt", b"written").unwrap();
+    assert_eq!(fs::read(dir.path().join("out.txt")).unwrap(), b"written");
+    assert_eq!(jail.read("missing").unwrap_err().kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn jail_rejects_parent_directories() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("root")).unwrap();
+    fs::write(dir.path().join("secret"), "secret").unwrap();
+    let mut jail = Jail::new(dir.path().join("root")).unwrap();
+
+    assert_eq!(jail.read("../secret").unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+    assert_eq!(jail.read("a/../../secret").unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+    assert_eq!(jail.write("../escaped", b"x").unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+    assert!(! dir.path().join("escaped").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn jail_rejects_symlink_escapes() {
+    use std::os::unix::fs::symlink;
+
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir(&root).unwrap();
+    fs::write(dir.path().join("secret"), "secret").unwrap();
+    symlink(dir.path().join("secret"), root.join("file_link")).unwrap();
+    symlink(dir.path(), root.join("dir_link")).unwrap();
+    symlink(dir.path().join("not_yet"), root.join("dangling")).unwrap();
+    symlink(root.join("inside.txt"), root.join("inner_link")).unwrap();
+    fs::write(root.join("inside.txt"), "inside").unwrap();
+    let mut jail = Jail::new(&root).unwrap();
+
+    assert_eq!(jail.read("file_link").unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+    assert_eq!(jail.read("dir_link/secret").unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+    assert_eq!(jail.write("dir_link/new", b"x").unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+    assert_eq!(jail.write("dangling", b"x").unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+    assert!(! dir.path().join("new").exists());
+    assert!(! dir.path().join("not_yet").exists());
+    assert_eq!(jail.read_to_string("inner_link").unwrap(), "inside");
+}
+
+#[test]
+fn jailed_executor() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("in.txt"), "hello").unwrap();
+    let mut e = Executor::new().with_file_system(Jail::new(dir.path()).unwrap());
+
+    assert_eq!(eval(&mut e, "fsread \"in.txt\""), Value::String("hello".to_string()));
+    assert_eq!(error(&mut e, "fsread \"../in.txt\""), ErrorKind::Io);
+    assert_eq!(error(&mut e, "fsread (+ \"..\" \"/in.txt\")"), ErrorKind::Io);
+}